@@ -0,0 +1,123 @@
+// Audio extraction: which codec yt-dlp converts to, and at what bitrate
+
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AudioCodec {
+    #[default]
+    Mp3,
+    /// AAC in an M4A container.
+    M4a,
+    Opus,
+    Flac,
+    Wav,
+    Vorbis,
+    /// The best audio stream as served, without re-encoding.
+    Original,
+}
+
+impl AudioCodec {
+    pub const ALL: [AudioCodec; 7] = [
+        AudioCodec::Mp3,
+        AudioCodec::M4a,
+        AudioCodec::Opus,
+        AudioCodec::Flac,
+        AudioCodec::Wav,
+        AudioCodec::Vorbis,
+        AudioCodec::Original,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AudioCodec::Mp3 => "MP3",
+            AudioCodec::M4a => "M4A (AAC)",
+            AudioCodec::Opus => "Opus",
+            AudioCodec::Flac => "FLAC",
+            AudioCodec::Wav => "WAV",
+            AudioCodec::Vorbis => "Vorbis",
+            AudioCodec::Original => "Original",
+        }
+    }
+
+    /// Value for yt-dlp's `--audio-format`; `None` keeps the source stream.
+    fn ytdlp_name(&self) -> Option<&'static str> {
+        match self {
+            AudioCodec::Mp3 => Some("mp3"),
+            AudioCodec::M4a => Some("m4a"),
+            AudioCodec::Opus => Some("opus"),
+            AudioCodec::Flac => Some("flac"),
+            AudioCodec::Wav => Some("wav"),
+            AudioCodec::Vorbis => Some("vorbis"),
+            AudioCodec::Original => None,
+        }
+    }
+
+    /// Bitrate only matters for lossy re-encodes.
+    pub fn has_bitrate(&self) -> bool {
+        !matches!(self, AudioCodec::Flac | AudioCodec::Wav | AudioCodec::Original)
+    }
+
+    /// File extension the result gets; `None` when it depends on the source.
+    pub fn ext(&self) -> Option<&'static str> {
+        match self {
+            AudioCodec::Vorbis => Some("ogg"),
+            other => other.ytdlp_name(),
+        }
+    }
+}
+
+impl FromStr for AudioCodec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "mp3" => Ok(AudioCodec::Mp3),
+            "m4a" | "aac" => Ok(AudioCodec::M4a),
+            "opus" => Ok(AudioCodec::Opus),
+            "flac" => Ok(AudioCodec::Flac),
+            "wav" => Ok(AudioCodec::Wav),
+            "vorbis" | "ogg" => Ok(AudioCodec::Vorbis),
+            "original" | "best" => Ok(AudioCodec::Original),
+            _ => Err(format!("unknown audio codec '{}'", s)),
+        }
+    }
+}
+
+/// How `Format::AudioOnly` downloads are converted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioOptions {
+    pub codec: AudioCodec,
+    /// Constant bitrate in kbit/s; `None` is the encoder's best VBR setting.
+    pub bitrate: Option<u32>,
+}
+
+impl AudioOptions {
+    /// Bitrates offered in the UI, in kbit/s.
+    pub const BITRATES: [u32; 7] = [320, 256, 192, 160, 128, 96, 64];
+
+    /// Short label used in history and status lines, e.g. `Opus 160k`.
+    pub fn label(&self) -> String {
+        match self.bitrate {
+            Some(k) if self.codec.has_bitrate() => format!("{} {}k", self.codec.label(), k),
+            _ => self.codec.label().to_string(),
+        }
+    }
+
+    /// yt-dlp arguments extracting audio with these options.
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec!["-x".to_string()];
+        if let Some(name) = self.codec.ytdlp_name() {
+            args.extend(["--audio-format".to_string(), name.to_string()]);
+        }
+        if self.codec.has_bitrate() {
+            let quality = match self.bitrate {
+                Some(k) => format!("{}K", k),
+                None => "0".into(),
+            };
+            args.extend(["--audio-quality".to_string(), quality]);
+        }
+        args
+    }
+}
@@ -0,0 +1,109 @@
+// Backends: the tool that does the transfer behind the queue, history and GUI.
+// yt-dlp takes video sites; plain file links can go straight over HTTP.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::download::{DownloadHandle, DownloadJob, Downloader};
+use crate::error::DownloadError;
+use crate::http::HttpBackend;
+use crate::info::FormatInfo;
+use crate::network::NetworkOptions;
+use crate::playlist::Media;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Something that can fetch media. A download reports through the
+/// `DownloadHandle` it returns, and is cancelled or paused through that
+/// handle's `Canceller`.
+pub trait Backend: Send + Sync {
+    /// Short name for the job list and history, e.g. `yt-dlp`.
+    fn name(&self) -> &'static str;
+
+    /// True if this backend should take `job`, given its URL and options.
+    fn handles(&self, job: &DownloadJob) -> bool;
+
+    /// Metadata for a single video, or the entries of a playlist.
+    fn inspect<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Media, DownloadError>>;
+
+    /// Formats `url` is offered in; none for a playlist.
+    fn formats<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Vec<FormatInfo>, DownloadError>> {
+        Box::pin(async move {
+            match self.inspect(url).await? {
+                Media::Video(info) => Ok(info.formats),
+                Media::Playlist(_) => Ok(Vec::new()),
+            }
+        })
+    }
+
+    /// Starts `job` on the current Tokio runtime. `Finished` is always the
+    /// last event.
+    fn start(&self, job: DownloadJob) -> DownloadHandle;
+
+    /// Why `job` can't run right now, e.g. a missing tool.
+    fn blocked(&self, _job: &DownloadJob) -> Option<DownloadError> {
+        None
+    }
+
+    /// Proxy, speed limit and retries for transfers started from now on.
+    fn set_network(&self, _network: &NetworkOptions) {}
+}
+
+impl Backend for Downloader {
+    fn name(&self) -> &'static str {
+        "yt-dlp"
+    }
+
+    fn handles(&self, _job: &DownloadJob) -> bool {
+        true
+    }
+
+    fn inspect<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Media, DownloadError>> {
+        Box::pin(Downloader::inspect(self, url))
+    }
+
+    fn start(&self, job: DownloadJob) -> DownloadHandle {
+        Downloader::start(self, job)
+    }
+
+    fn blocked(&self, job: &DownloadJob) -> Option<DownloadError> {
+        Downloader::blocked(self, job)
+    }
+
+    fn set_network(&self, network: &NetworkOptions) {
+        Downloader::set_network(self, network.clone());
+    }
+}
+
+/// Backends tried in order; the first that handles a job gets it, and the
+/// last one takes whatever the others don't. Cheap to clone.
+#[derive(Clone)]
+pub struct Backends(Vec<Arc<dyn Backend>>);
+
+impl Backends {
+    /// # Panics
+    /// If `backends` is empty.
+    pub fn new(backends: Vec<Arc<dyn Backend>>) -> Self {
+        assert!(!backends.is_empty(), "at least one backend is needed");
+        Self(backends)
+    }
+
+    /// Direct HTTP for plain downloads of file links, `downloader` for everything else.
+    pub fn standard(downloader: Downloader) -> Self {
+        let http = HttpBackend::default();
+        http.set_network(&downloader.network());
+        Self::new(vec![Arc::new(http), Arc::new(downloader)])
+    }
+
+    pub fn pick(&self, job: &DownloadJob) -> &dyn Backend {
+        let last = self.0.last().expect("at least one backend");
+        self.0.iter().find(|b| b.handles(job)).unwrap_or(last).as_ref()
+    }
+
+    pub fn set_network(&self, network: &NetworkOptions) {
+        for backend in &self.0 {
+            backend.set_network(network);
+        }
+    }
+}
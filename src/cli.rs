@@ -0,0 +1,445 @@
+// Headless mode: `rstube download|history|queue` run the same jobs as the GUI
+// and report progress on the terminal
+
+use clap::{Args, Parser, Subcommand};
+use rstube::units::{human_bytes, human_duration};
+use rstube::{
+    AudioCodec, Backends, CancelRequest, Container, DownloadError, Downloader, FailureKind, Format, History, JobState,
+    Queue, Section, Settings, Toolchain,
+};
+use std::collections::HashSet;
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+const EXIT_CODES: &str = "\
+Exit codes:
+  0    all downloads finished
+  1    a download failed
+  2    invalid arguments
+  3    yt-dlp could not be started
+  4    video unavailable (private, age-restricted, members-only, geo-blocked or unsupported URL)
+  5    rate limited by the site
+  6    ffmpeg missing
+  7    disk full
+  130  interrupted with Ctrl-C; `download` cancels unfinished jobs, `queue --run` pauses them";
+
+pub mod exit {
+    pub const OK: u8 = 0;
+    pub const FAILED: u8 = 1;
+    pub const USAGE: u8 = 2;
+    pub const NO_YTDLP: u8 = 3;
+    pub const UNAVAILABLE: u8 = 4;
+    pub const RATE_LIMITED: u8 = 5;
+    pub const NO_FFMPEG: u8 = 6;
+    pub const DISK_FULL: u8 = 7;
+    pub const INTERRUPTED: u8 = 130;
+}
+
+#[derive(Parser)]
+#[command(name = "rstube", version, about = "YouTube downloader built on yt-dlp", after_help = EXIT_CODES)]
+pub struct Cli {
+    /// Without a command the GUI starts.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Download one or more URLs
+    Download(Box<DownloadArgs>),
+    /// Show past downloads, newest first
+    History {
+        /// Number of entries to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Show the jobs saved by the GUI
+    Queue {
+        /// Download the pending jobs; paused ones stay paused
+        #[arg(long)]
+        run: bool,
+    },
+    /// Show where yt-dlp, ffmpeg and ffprobe are and their versions
+    Tools,
+}
+
+#[derive(Args)]
+pub struct DownloadArgs {
+    #[arg(required_unless_present = "batch_file")]
+    urls: Vec<String>,
+    /// Also read URLs from a text or CSV file, one per line,
+    /// optionally followed by a format (`url audio`, `url 137+140`,
+    /// or CSV with a `url,format` header row)
+    #[arg(short = 'a', long)]
+    batch_file: Option<PathBuf>,
+    /// `video`, `audio`, or a format id pair such as `137+140`
+    /// [default: from settings]
+    #[arg(short, long)]
+    format: Option<Format>,
+    /// Output folder [default: from settings]
+    #[arg(short, long)]
+    out: Option<PathBuf>,
+    /// Parallel downloads [default: from settings]
+    #[arg(short, long)]
+    jobs: Option<usize>,
+    /// File name template such as `{uploader} - {title}.{ext}`
+    /// [default: from settings]
+    #[arg(short, long, value_parser = parse_template)]
+    template: Option<String>,
+    /// Largest video height, e.g. 1080 [default: from settings]
+    #[arg(long, value_name = "HEIGHT")]
+    max_res: Option<u32>,
+    /// Video container: mp4, mkv or webm [default: from settings]
+    #[arg(long)]
+    container: Option<Container>,
+    /// Codec for `--format audio`: mp3, m4a, opus, flac, wav, vorbis or
+    /// original [default: from settings]
+    #[arg(long, value_name = "CODEC")]
+    audio_codec: Option<AudioCodec>,
+    /// Audio bitrate in kbit/s; 0 for the best VBR quality [default: from settings]
+    #[arg(long, value_name = "KBPS")]
+    audio_bitrate: Option<u32>,
+    /// Only download this time range, e.g. `1:00-2:30` or `90-`; repeatable
+    #[arg(long = "section", value_name = "START-END", value_parser = rstube::sections::parse_range)]
+    sections: Vec<Section>,
+    /// Only download the chapter with this title; repeatable
+    #[arg(long = "chapter", value_name = "TITLE")]
+    chapters: Vec<String>,
+    /// Subtitle languages such as `en,de`, or `none` [default: from settings]
+    #[arg(long, value_name = "LANGS")]
+    subs: Option<String>,
+    /// Cookie profile imported in the GUI, or `none` to send no cookies
+    /// [default: the profile covering each site]
+    #[arg(long, value_name = "PROFILE")]
+    cookies: Option<String>,
+    /// HTTP or SOCKS proxy such as `socks5://127.0.0.1:1080`, or `none`
+    /// [default: from settings]
+    #[arg(long, value_name = "URL")]
+    proxy: Option<String>,
+    /// Download speed cap in KiB/s; 0 for none [default: from settings]
+    #[arg(long, value_name = "KBPS")]
+    limit_rate: Option<u32>,
+}
+
+pub fn run(command: Command, settings: &Settings) -> u8 {
+    match command {
+        Command::Download(args) => download(*args, settings),
+        Command::History { limit } => history(limit),
+        Command::Queue { run } => queue(run, settings),
+        Command::Tools => tools(settings),
+    }
+}
+
+fn parse_template(s: &str) -> Result<String, String> {
+    rstube::template::validate(s).map(|()| s.to_string())
+}
+
+/// yt-dlp as found through `settings`, plus direct HTTP for file links, with
+/// the network options applied. Warns about old or missing tools; jobs that
+/// need a missing one fail when they start.
+fn backends(rt: &Runtime, settings: &Settings) -> Backends {
+    let tools = rt.block_on(Toolchain::detect(&settings.tools));
+    for warning in tools.warnings() {
+        eprintln!("warning: {}", warning);
+    }
+    let downloader = Downloader::default()
+        .with_toolchain(&tools)
+        .with_network(settings.network.clone());
+    Backends::standard(downloader)
+}
+
+impl DownloadArgs {
+    /// `settings` with the options given on the command line applied.
+    fn apply(&self, settings: &Settings) -> Settings {
+        let mut settings = settings.clone();
+        settings.format = self.format.clone().unwrap_or(settings.format);
+        settings.output_dir = self.out.clone().or(settings.output_dir);
+        settings.concurrency = self.jobs.unwrap_or(settings.concurrency);
+        settings.filename_template = self.template.clone().unwrap_or(settings.filename_template);
+        settings.video.max_height = self.max_res.or(settings.video.max_height);
+        settings.video.container = self.container.unwrap_or(settings.video.container);
+        settings.audio.codec = self.audio_codec.unwrap_or(settings.audio.codec);
+        if let Some(k) = self.audio_bitrate {
+            settings.audio.bitrate = Some(k).filter(|k| *k > 0);
+        }
+        if let Some(subs) = &self.subs {
+            settings.subtitles.languages = subs
+                .split(',')
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && *l != "none")
+                .map(String::from)
+                .collect();
+        }
+        match self.proxy.as_deref() {
+            Some("none") => settings.network.proxy.clear(),
+            Some(proxy) => settings.network.proxy = proxy.to_string(),
+            None => {}
+        }
+        if let Some(k) = self.limit_rate {
+            settings.network.rate_limit = Some(k).filter(|k| *k > 0);
+        }
+        match self.cookies.as_deref() {
+            Some("none") => settings.cookie_profiles.clear(),
+            Some(name) => settings.active_cookies = Some(name.to_string()),
+            None => {}
+        }
+        settings
+    }
+}
+
+fn download(args: DownloadArgs, settings: &Settings) -> u8 {
+    let settings = &args.apply(settings);
+    if let Some(name) = &args.cookies
+        && name != "none"
+        && !settings.cookie_profiles.iter().any(|p| p.name == *name)
+    {
+        eprintln!("No cookie profile named '{}'. Import one in the GUI under Authentication.", name);
+        return exit::USAGE;
+    }
+    if let Err(e) = settings.network.validate() {
+        eprintln!("Invalid network settings: {}", e);
+        return exit::USAGE;
+    }
+    let mut sections = args.sections;
+    sections.extend(args.chapters.into_iter().map(Section::Chapter));
+    let format = &settings.format;
+    let mut targets: Vec<(String, Format)> = args.urls.into_iter().map(|u| (u, format.clone())).collect();
+
+    if let Some(path) = args.batch_file {
+        let report = match rstube::import::read(&path) {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("Could not read {}: {}", path.display(), e);
+                return exit::USAGE;
+            }
+        };
+        eprintln!("{} from {}", report.summary(), path.display());
+        for r in &report.rejected {
+            eprintln!("  line {}: {} — {}", r.line, r.text, r.reason);
+        }
+        for entry in report.entries {
+            if !targets.iter().any(|(url, _)| *url == entry.url) {
+                targets.push((entry.url, entry.format.unwrap_or_else(|| format.clone())));
+            }
+        }
+    }
+
+    if targets.is_empty() {
+        eprintln!("Nothing to download.");
+        return exit::USAGE;
+    }
+
+    let rt = Runtime::new().expect("Tokio runtime");
+    let queue = Queue::new(backends(&rt, settings), open_history(), rt.handle().clone(), settings.concurrency);
+
+    for (url, format) in targets {
+        queue.push(settings.job(url, format).sections(sections.clone()));
+    }
+
+    wait(&rt, &queue, false)
+}
+
+fn history(limit: usize) -> u8 {
+    let items = open_history().items();
+    if items.is_empty() {
+        println!("No downloads yet.");
+    }
+
+    for item in items.iter().rev().take(limit) {
+        let started = item.started_at.with_timezone(&chrono::Local);
+        let size = item.bytes.map(human_bytes).unwrap_or_else(|| "-".into());
+        let took = human_duration(item.duration().num_seconds().max(0) as u64);
+        let lock = if item.auth_failure { "🔒" } else { " " };
+        println!(
+            "{} {} {:<24} {:<10} {:>10} {:>8}  {}",
+            started.format("%Y-%m-%d %H:%M"),
+            lock,
+            item.status,
+            item.format,
+            size,
+            took,
+            item.display_name()
+        );
+    }
+    exit::OK
+}
+
+fn queue(run: bool, settings: &Settings) -> u8 {
+    let Some(path) = Queue::default_store_path() else {
+        eprintln!("No data directory available on this system.");
+        return exit::FAILED;
+    };
+
+    if !run {
+        return match rstube::queue::read_saved(&path) {
+            Ok(saved) if saved.is_empty() => {
+                println!("Queue is empty.");
+                exit::OK
+            }
+            Ok(saved) => {
+                for s in saved {
+                    let state = if s.paused { "Paused" } else { "Pending" };
+                    println!("{:<8} {:<12} {}", state, s.job.format_label(), s.job.display_name());
+                }
+                exit::OK
+            }
+            Err(e) => {
+                eprintln!("Could not read {}: {}", path.display(), e);
+                exit::FAILED
+            }
+        };
+    }
+
+    let rt = Runtime::new().expect("Tokio runtime");
+    let queue = Queue::new(backends(&rt, settings), open_history(), rt.handle().clone(), settings.concurrency);
+    if let Err(e) = queue.persist_to(&path) {
+        eprintln!("Could not read {}: {}", path.display(), e);
+        return exit::FAILED;
+    }
+    wait(&rt, &queue, true)
+}
+
+fn tools(settings: &Settings) -> u8 {
+    let rt = Runtime::new().expect("Tokio runtime");
+    let tools = rt.block_on(Toolchain::detect(&settings.tools));
+    for status in tools.tools() {
+        let mark = if status.is_ok() { "✔" } else { "✘" };
+        let version = status.version.as_deref().unwrap_or("-");
+        match (&status.path, status.source) {
+            (Some(path), Some(source)) => println!(
+                "{} {:<8} {:<20} {} ({})",
+                mark,
+                status.tool.name(),
+                version,
+                path.display(),
+                source.label()
+            ),
+            _ => println!("{} {:<8} {:<20} not found", mark, status.tool.name(), version),
+        }
+    }
+    for warning in tools.warnings() {
+        println!("warning: {}", warning);
+    }
+    if tools.yt_dlp.is_ok() { exit::OK } else { exit::NO_YTDLP }
+}
+
+fn open_history() -> History {
+    match History::default_path().map(History::open) {
+        Some(Ok(history)) => history,
+        Some(Err(e)) => {
+            eprintln!("Could not load history: {}", e);
+            History::in_memory()
+        }
+        None => History::in_memory(),
+    }
+}
+
+/// Prints progress until the queue is idle and returns the exit code.
+/// Reports progress until the queue is idle. On Ctrl-C, jobs of a queue
+/// saved to disk are paused so they can be continued; others are cancelled.
+fn wait(rt: &Runtime, queue: &Queue, saved: bool) -> u8 {
+    let interactive = std::io::stdout().is_terminal();
+    let mut reported = HashSet::new();
+    let mut code = exit::OK;
+
+    let interrupted = rt.block_on(async {
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+        loop {
+            tokio::select! {
+                _ = &mut ctrl_c => return true,
+                _ = tokio::time::sleep(Duration::from_millis(250)) => {}
+            }
+            // Checked before reporting, so a job that finishes in between
+            // is still printed and counted.
+            let idle = queue.is_idle();
+            report(queue, &mut reported, &mut code, interactive);
+            if idle {
+                return false;
+            }
+        }
+    });
+
+    if interrupted {
+        if saved {
+            eprintln!("\nInterrupted, pausing downloads…");
+        } else {
+            eprintln!("\nInterrupted, stopping downloads…");
+        }
+        // Newest first, so waiting jobs are stopped before a running one
+        // finishes and frees its slot for them.
+        for entry in queue.jobs().iter().rev() {
+            if saved {
+                queue.pause(entry.id);
+            } else {
+                queue.cancel(entry.id, CancelRequest { delete_partials: false });
+            }
+        }
+        rt.block_on(async {
+            while !queue.is_idle() {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        });
+        return exit::INTERRUPTED;
+    }
+    code
+}
+
+fn report(queue: &Queue, reported: &mut HashSet<u64>, code: &mut u8, interactive: bool) {
+    let mut running = Vec::new();
+
+    for entry in queue.jobs() {
+        match entry.state {
+            JobState::Running => {
+                let pct = entry.progress * 100.0;
+                let transfer = entry.transfer.as_ref().map(|t| t.summary()).unwrap_or_default();
+                running.push(format!("#{} {:.1}% {}", entry.id, pct, transfer));
+            }
+            state if state.is_finished() && reported.insert(entry.id) => {
+                if interactive {
+                    print!("\r\x1b[2K");
+                }
+                match &entry.error {
+                    None if state == JobState::Done => println!("✔ #{} {}", entry.id, entry.job.display_name()),
+                    None => println!("✖ #{} {} — {}", entry.id, entry.job.display_name(), state.label()),
+                    Some(e) => {
+                        println!("✘ #{} {} — {}", entry.id, entry.job.display_name(), e);
+                        if let Some(hint) = e.hint() {
+                            println!("  {}", hint);
+                        }
+                        if *code == exit::OK {
+                            *code = exit_code(e);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if interactive && !running.is_empty() {
+        print!("\r\x1b[2K{}", running.join("  |  "));
+        let _ = std::io::stdout().flush();
+    }
+}
+
+fn exit_code(error: &DownloadError) -> u8 {
+    match error {
+        DownloadError::Spawn(_) => exit::NO_YTDLP,
+        DownloadError::Failed { kind, .. } => match kind {
+            FailureKind::PrivateVideo
+            | FailureKind::AgeRestricted
+            | FailureKind::LoginRequired
+            | FailureKind::GeoBlocked
+            | FailureKind::UnsupportedUrl => exit::UNAVAILABLE,
+            FailureKind::RateLimited => exit::RATE_LIMITED,
+            FailureKind::FfmpegMissing => exit::NO_FFMPEG,
+            FailureKind::DiskFull => exit::DISK_FULL,
+            FailureKind::Unknown => exit::FAILED,
+        },
+        DownloadError::Unreadable(_) => exit::FAILED,
+        DownloadError::Cancelled => exit::INTERRUPTED,
+    }
+}
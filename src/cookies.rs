@@ -0,0 +1,172 @@
+// Netscape cookies.txt files for age-gated and members-only videos, kept as
+// named profiles so each site can use the account it needs
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use crate::store;
+
+/// A cookies.txt file copied into the data directory under a name.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CookieProfile {
+    pub name: String,
+    pub file: PathBuf,
+    /// Domains the file has cookies for, without the leading dot.
+    pub domains: Vec<String>,
+}
+
+impl CookieProfile {
+    /// True when the profile has cookies for the host of `url`.
+    pub fn covers(&self, url: &str) -> bool {
+        let Some(host) = url::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_lowercase)) else {
+            return false;
+        };
+        self.domains
+            .iter()
+            .any(|d| host == *d || host.ends_with(&format!(".{}", d)))
+    }
+}
+
+/// What a cookies.txt file holds for one domain.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DomainCookies {
+    pub domain: String,
+    pub count: usize,
+    /// When the first of them expires; `None` if all last only for the session.
+    pub expires: Option<DateTime<Utc>>,
+}
+
+impl DomainCookies {
+    pub fn is_expired(&self) -> bool {
+        self.expires.is_some_and(|e| e < Utc::now())
+    }
+}
+
+/// Summarises a Netscape cookies.txt file by domain.
+pub fn parse(text: &str) -> Result<Vec<DomainCookies>, String> {
+    let mut domains: BTreeMap<String, DomainCookies> = BTreeMap::new();
+
+    for (i, line) in text.lines().enumerate() {
+        // Browsers export HTTP-only cookies with this prefix instead of as comments.
+        let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 7 {
+            return Err(format!(
+                "line {} has {} fields instead of 7; is this a Netscape cookies.txt file?",
+                i + 1,
+                fields.len()
+            ));
+        }
+        let domain = fields[0].trim_start_matches('.').to_lowercase();
+        let expires = match fields[4].trim().parse::<i64>() {
+            Ok(0) => None,
+            Ok(secs) => DateTime::from_timestamp(secs, 0),
+            Err(_) => return Err(format!("line {}: '{}' is not an expiry time", i + 1, fields[4])),
+        };
+
+        let entry = domains.entry(domain.clone()).or_insert(DomainCookies {
+            domain,
+            count: 0,
+            expires: None,
+        });
+        entry.count += 1;
+        if let Some(e) = expires {
+            entry.expires = Some(entry.expires.map_or(e, |old| old.min(e)));
+        }
+    }
+
+    if domains.is_empty() {
+        return Err("the file has no cookies".into());
+    }
+    Ok(domains.into_values().collect())
+}
+
+/// Where imported cookie files are kept.
+pub fn profiles_dir() -> Option<PathBuf> {
+    store::data_dir().map(|d| d.join("cookies"))
+}
+
+/// Reads and summarises a cookies.txt file.
+pub fn summarize(file: &Path) -> io::Result<Vec<DomainCookies>> {
+    let text = fs::read_to_string(file)?;
+    parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Checks `source`, copies it into `dir` as `<name>.txt` and returns the
+/// profile with what the file covers. Importing under an existing name
+/// replaces that profile's file.
+pub fn import(source: &Path, name: &str, dir: &Path) -> io::Result<(CookieProfile, Vec<DomainCookies>)> {
+    let text = fs::read_to_string(source)?;
+    let summary = parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let slug: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    let file = dir.join(format!("{}.txt", slug));
+    store::write_atomic(&file, text.as_bytes())?;
+    // Cookies are as good as a password to the account they came from.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o600))?;
+    }
+
+    let profile = CookieProfile {
+        name: name.to_string(),
+        file,
+        domains: summary.iter().map(|d| d.domain.clone()).collect(),
+    };
+    Ok((profile, summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_cookies_by_domain() {
+        let text = "# Netscape HTTP Cookie File\n\
+            .youtube.com\tTRUE\t/\tTRUE\t1900000000\tSID\tabc\n\
+            #HttpOnly_.youtube.com\tTRUE\t/\tTRUE\t1800000000\tHSID\tdef\n\
+            \n\
+            example.org\tFALSE\t/\tFALSE\t0\tsession\txyz\n";
+        let domains = parse(text).unwrap();
+
+        assert_eq!(domains.len(), 2);
+        assert_eq!(domains[0].domain, "example.org");
+        assert_eq!(domains[0].count, 1);
+        // Session cookies have no expiry.
+        assert_eq!(domains[0].expires, None);
+        assert_eq!(domains[1].domain, "youtube.com");
+        assert_eq!(domains[1].count, 2);
+        assert_eq!(domains[1].expires, DateTime::from_timestamp(1_800_000_000, 0));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(parse(".youtube.com\tTRUE\t/\tTRUE\t1900000000\tSID\n").is_err());
+        assert!(parse("{\"cookies\": []}\n").is_err());
+        assert!(parse(".youtube.com\tTRUE\t/\tTRUE\tsoon\tSID\tabc\n").is_err());
+        assert!(parse("# only comments\n").is_err());
+    }
+
+    #[test]
+    fn profiles_cover_subdomains() {
+        let profile = CookieProfile {
+            name: "main".into(),
+            file: PathBuf::from("main.txt"),
+            domains: vec!["youtube.com".into()],
+        };
+        assert!(profile.covers("https://www.youtube.com/watch?v=abc"));
+        assert!(profile.covers("https://youtube.com/"));
+        assert!(!profile.covers("https://notyoutube.com/"));
+    }
+}
@@ -0,0 +1,584 @@
+// Download engine: runs yt-dlp for a job and reports typed progress events
+
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, path::PathBuf, process::Stdio, sync::{Arc, Mutex}};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, watch};
+
+use crate::audio::AudioOptions;
+use crate::cookies::CookieProfile;
+use crate::embed::EmbedOptions;
+use crate::error::{DownloadError, FailureKind};
+use crate::format::Format;
+use crate::info::VideoInfo;
+use crate::network::NetworkOptions;
+use crate::playlist::Media;
+use crate::process;
+use crate::progress::{self, ProgressEvent};
+use crate::sections::{self, Section};
+use crate::sponsorblock::SponsorBlockOptions;
+use crate::subtitles::{self, SubtitleOptions};
+use crate::template::{self, PlaylistPosition};
+use crate::tools::Toolchain;
+use crate::video::VideoOptions;
+
+/// Everything needed to run one download.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DownloadJob {
+    pub url: String,
+    pub format: Format,
+    /// Stream limits and container for `Format::BestVideo`.
+    #[serde(default)]
+    pub video: VideoOptions,
+    /// Conversion for `Format::AudioOnly`; MP3 for jobs saved before it existed.
+    #[serde(default)]
+    pub audio: AudioOptions,
+    pub output_dir: Option<PathBuf>,
+    /// Path below `output_dir`, with `{title}`-style placeholders; see
+    /// [`template`](crate::template). yt-dlp's default when unset.
+    #[serde(default)]
+    pub output_template: Option<String>,
+    /// Set when the job was picked from a playlist.
+    #[serde(default)]
+    pub playlist: Option<PlaylistPosition>,
+    #[serde(default)]
+    pub subtitles: Option<SubtitleOptions>,
+    /// Cover art, tags and chapters written into the file.
+    #[serde(default)]
+    pub embed: EmbedOptions,
+    /// SponsorBlock segments to cut out or mark.
+    #[serde(default)]
+    pub sponsorblock: SponsorBlockOptions,
+    /// Signed-in session for age-gated or members-only videos.
+    #[serde(default)]
+    pub cookies: Option<CookieProfile>,
+    /// Parts of the video to fetch; empty for all of it.
+    #[serde(default)]
+    pub sections: Vec<Section>,
+    /// Video title from a metadata probe, shown instead of the URL.
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+impl DownloadJob {
+    pub fn new(url: impl Into<String>, format: Format) -> Self {
+        Self {
+            url: url.into(),
+            format,
+            video: VideoOptions::default(),
+            audio: AudioOptions::default(),
+            output_dir: None,
+            output_template: None,
+            playlist: None,
+            subtitles: None,
+            embed: EmbedOptions::default(),
+            sponsorblock: SponsorBlockOptions::default(),
+            cookies: None,
+            sections: Vec::new(),
+            title: None,
+        }
+    }
+
+    pub fn video(mut self, options: VideoOptions) -> Self {
+        self.video = options;
+        self
+    }
+
+    pub fn audio(mut self, options: AudioOptions) -> Self {
+        self.audio = options;
+        self
+    }
+
+    pub fn output_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = Some(dir.into());
+        self
+    }
+
+    pub fn output_template(mut self, template: impl Into<String>) -> Self {
+        self.output_template = Some(template.into());
+        self
+    }
+
+    pub fn playlist(mut self, position: PlaylistPosition) -> Self {
+        self.playlist = Some(position);
+        self
+    }
+
+    /// Fetches subtitles as well; options without languages are ignored.
+    pub fn subtitles(mut self, options: SubtitleOptions) -> Self {
+        self.subtitles = Some(options).filter(SubtitleOptions::is_enabled);
+        self
+    }
+
+    pub fn embed(mut self, options: EmbedOptions) -> Self {
+        self.embed = options;
+        self
+    }
+
+    pub fn sponsorblock(mut self, options: SponsorBlockOptions) -> Self {
+        self.sponsorblock = options;
+        self
+    }
+
+    pub fn cookies(mut self, profile: CookieProfile) -> Self {
+        self.cookies = Some(profile);
+        self
+    }
+
+    pub fn sections(mut self, sections: Vec<Section>) -> Self {
+        self.sections = sections;
+        self
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Title when known, otherwise the URL.
+    pub fn display_name(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.url)
+    }
+
+    /// What in this job needs ffmpeg, if anything: merging, converting or
+    /// cutting all run through it.
+    pub fn needs_ffmpeg(&self) -> Option<&'static str> {
+        if matches!(&self.format, Format::BestVideo | Format::Custom { video: Some(_), audio: Some(_) }) {
+            return Some("Merging video and audio");
+        }
+        if self.format == Format::AudioOnly {
+            return Some("Converting audio");
+        }
+        if self.embed.thumbnail || self.embed.metadata || self.embed.chapters {
+            return Some("Embedding cover art, tags or chapters");
+        }
+        if self.sponsorblock.is_enabled() {
+            return Some("SponsorBlock");
+        }
+        if !self.sections.is_empty() {
+            return Some("Downloading part of a video");
+        }
+        if self.subtitles.is_some() {
+            return Some("Converting subtitles");
+        }
+        None
+    }
+
+    /// Format label including the audio codec for audio downloads, and
+    /// the sections when only part of the video is fetched.
+    pub fn format_label(&self) -> String {
+        let label = match self.format {
+            Format::AudioOnly => self.audio.label(),
+            _ => self.format.label(),
+        };
+        if self.sections.is_empty() {
+            return label;
+        }
+        let sections: Vec<String> = self.sections.iter().map(Section::to_string).collect();
+        format!("{} ({})", label, sections.join(", "))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum DownloadEvent {
+    Started,
+    Progress(ProgressEvent),
+    /// File yt-dlp is writing to; the last one reported is the final output.
+    Output(PathBuf),
+    /// Any other line yt-dlp printed.
+    Log(String),
+    /// A line yt-dlp wrote to stderr.
+    Stderr(String),
+    Finished(Result<(), DownloadError>),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CancelRequest {
+    /// Remove the `.part` files yt-dlp leaves behind.
+    pub delete_partials: bool,
+}
+
+/// Stops a running download and every process it started. Cheap to clone.
+#[derive(Clone)]
+pub struct Canceller(Arc<watch::Sender<Option<CancelRequest>>>);
+
+impl Canceller {
+    fn new() -> Self {
+        Self(Arc::new(watch::channel(None).0))
+    }
+
+    pub fn cancel(&self, request: CancelRequest) {
+        self.0.send_replace(Some(request));
+    }
+
+    /// Resolves once a cancel has been requested, straight away if one already was.
+    pub async fn cancelled(&self) -> CancelRequest {
+        cancelled(&mut self.0.subscribe()).await
+    }
+}
+
+/// Receiving end of a running download. `Finished` is always the last event.
+pub struct DownloadHandle {
+    events: mpsc::UnboundedReceiver<DownloadEvent>,
+    canceller: Canceller,
+}
+
+impl DownloadHandle {
+    /// A handle for a new download, and the sender its backend reports
+    /// events through.
+    pub fn channel() -> (Self, mpsc::UnboundedSender<DownloadEvent>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let handle = Self {
+            events: rx,
+            canceller: Canceller::new(),
+        };
+        (handle, tx)
+    }
+
+    pub async fn next(&mut self) -> Option<DownloadEvent> {
+        self.events.recv().await
+    }
+
+    pub fn canceller(&self) -> Canceller {
+        self.canceller.clone()
+    }
+}
+
+/// How yt-dlp is run; changed through `Downloader::set_*`.
+#[derive(Clone, Debug)]
+struct Config {
+    program: PathBuf,
+    network: NetworkOptions,
+    /// Passed as `--ffmpeg-location` when ffmpeg isn't on PATH.
+    ffmpeg_location: Option<PathBuf>,
+    /// Set once detection has found no ffmpeg; unknown counts as present.
+    ffmpeg_missing: bool,
+    /// Set once detection has found no working yt-dlp.
+    program_missing: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Downloader {
+    /// Shared between clones, so a change reaches the queue's copy too.
+    config: Arc<Mutex<Config>>,
+}
+
+impl Default for Downloader {
+    fn default() -> Self {
+        Self::new("yt-dlp")
+    }
+}
+
+impl Downloader {
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            config: Arc::new(Mutex::new(Config {
+                program: program.into(),
+                network: NetworkOptions::default(),
+                ffmpeg_location: None,
+                ffmpeg_missing: false,
+                program_missing: false,
+            })),
+        }
+    }
+
+    pub fn with_network(self, network: NetworkOptions) -> Self {
+        self.set_network(network);
+        self
+    }
+
+    /// Network options for runs started from now on.
+    pub fn set_network(&self, network: NetworkOptions) {
+        self.config.lock().unwrap().network = network;
+    }
+
+    pub fn network(&self) -> NetworkOptions {
+        self.config.lock().unwrap().network.clone()
+    }
+
+    pub fn with_toolchain(self, tools: &Toolchain) -> Self {
+        self.set_toolchain(tools);
+        self
+    }
+
+    /// Uses the yt-dlp and ffmpeg that `tools` found. Without ffmpeg, jobs
+    /// that need it fail straight away instead of after downloading.
+    pub fn set_toolchain(&self, tools: &Toolchain) {
+        let mut config = self.config.lock().unwrap();
+        if let Some(path) = &tools.yt_dlp.path {
+            config.program = path.clone();
+        }
+        config.ffmpeg_location = tools.ffmpeg_location();
+        config.ffmpeg_missing = !tools.has_ffmpeg();
+        config.program_missing = !tools.yt_dlp.is_ok();
+    }
+
+    /// Why `job` can't run with the tools found: no yt-dlp, or no ffmpeg
+    /// for a job that needs it.
+    pub fn blocked(&self, job: &DownloadJob) -> Option<DownloadError> {
+        let config = self.config.lock().unwrap();
+        if config.program_missing {
+            return Some(DownloadError::Spawn(format!("{} was not found", config.program.display())));
+        }
+        let reason = job.needs_ffmpeg().filter(|_| config.ffmpeg_missing)?;
+        Some(DownloadError::Failed {
+            kind: FailureKind::FfmpegMissing,
+            exit_code: None,
+            message: Some(format!("{} needs ffmpeg and ffprobe, which were not found", reason)),
+        })
+    }
+
+    /// yt-dlp with the options every invocation shares.
+    fn base_command(&self) -> Command {
+        let config = self.config.lock().unwrap();
+        let mut cmd = Command::new(&config.program);
+        if let Some(dir) = &config.ffmpeg_location {
+            cmd.arg("--ffmpeg-location").arg(dir);
+        }
+        cmd.args(config.network.args());
+        cmd
+    }
+
+    /// Fetches a single video's metadata without downloading it.
+    pub async fn probe(&self, url: &str) -> Result<VideoInfo, DownloadError> {
+        let mut cmd = self.base_command();
+        cmd.args(["--dump-json", "--no-playlist", "--skip-download"]).arg(url);
+        let stdout = capture(&mut cmd).await?;
+        VideoInfo::from_json(&stdout).map_err(|e| DownloadError::Unreadable(e.to_string()))
+    }
+
+    /// Fetches a URL that may be a single video, a playlist or a channel.
+    /// Playlists are expanded flat, so each entry carries only basic fields.
+    pub async fn inspect(&self, url: &str) -> Result<Media, DownloadError> {
+        let mut cmd = self.base_command();
+        cmd.args(["--dump-single-json", "--flat-playlist", "--skip-download"]).arg(url);
+        let stdout = capture(&mut cmd).await?;
+        Media::from_json(&stdout).map_err(|e| DownloadError::Unreadable(e.to_string()))
+    }
+
+    /// Builds the yt-dlp command line for a job without running it.
+    pub fn command(&self, job: &DownloadJob) -> Command {
+        let mut cmd = self.base_command();
+        // --continue picks up the .part file left by a paused or interrupted run.
+        cmd.args(["--newline", "--continue"]);
+        // A job is one video; a watch link that also names a playlist must not pull in the rest.
+        cmd.arg("--no-playlist");
+        cmd.arg("--progress-template").arg(progress::template());
+
+        if let Some(d) = &job.output_dir {
+            cmd.arg("-P").arg(d);
+        }
+        let mut output = job.output_template.as_ref().map(|t| template::to_ytdlp(t, job.playlist.as_ref()));
+        if !job.sections.is_empty() {
+            output = Some(sections::tag_output(output.as_deref().unwrap_or(template::YTDLP_DEFAULT)));
+        }
+        if let Some(t) = output {
+            cmd.arg("-o").arg(t);
+        }
+
+        if let Some(profile) = &job.cookies {
+            cmd.arg("--cookies").arg(&profile.file);
+        }
+        cmd.args(job.format.args(&job.video, &job.audio));
+        cmd.args(job.embed.args());
+        cmd.args(job.sponsorblock.args());
+        for section in &job.sections {
+            cmd.arg("--download-sections").arg(section.arg());
+        }
+        if let Some(subs) = &job.subtitles {
+            let mut subs = subs.clone();
+            // There is no video stream to embed them in.
+            subs.embed &= !job.format.is_audio_only();
+            cmd.args(subs.args());
+        }
+        cmd.arg(&job.url);
+        cmd
+    }
+
+    /// Spawns the download on the current Tokio runtime.
+    pub fn start(&self, job: DownloadJob) -> DownloadHandle {
+        let (handle, tx) = DownloadHandle::channel();
+        let mut cmd = self.command(&job);
+        let blocked = self.blocked(&job);
+
+        let task_canceller = handle.canceller();
+        tokio::spawn(async move {
+            if let Some(e) = blocked {
+                let _ = tx.send(DownloadEvent::Finished(Err(e)));
+                return;
+            }
+            let mut subtitle_files = Vec::new();
+            let result = run(&mut cmd, &tx, &task_canceller, &mut subtitle_files).await;
+            if result.is_ok()
+                && let Some(options) = &job.subtitles
+            {
+                finish_subtitles(options, &subtitle_files, &tx);
+            }
+            let _ = tx.send(DownloadEvent::Finished(result));
+        });
+
+        handle
+    }
+}
+
+async fn run(
+    cmd: &mut Command,
+    tx: &mpsc::UnboundedSender<DownloadEvent>,
+    canceller: &Canceller,
+    subtitle_files: &mut Vec<PathBuf>,
+) -> Result<(), DownloadError> {
+    let mut cancel = canceller.0.subscribe();
+    if cancel.borrow_and_update().is_some() {
+        return Err(DownloadError::Cancelled);
+    }
+
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).kill_on_drop(true);
+    process::isolate(cmd);
+
+    let mut child = cmd.spawn().map_err(|e| DownloadError::Spawn(e.to_string()))?;
+    let _ = tx.send(DownloadEvent::Started);
+
+    // Read stderr alongside stdout so a chatty run can't fill the pipe and stall.
+    let stderr = tokio::spawn(drain_stderr(child.stderr.take().unwrap(), tx.clone()));
+
+    let stdout = child.stdout.take().unwrap();
+    let mut reader = BufReader::new(stdout).lines();
+    let mut destinations = Vec::new();
+
+    let exit = async {
+        loop {
+            tokio::select! {
+                line = reader.next_line() => {
+                    let Ok(Some(line)) = line else { break };
+                    if let Some(dest) = parse_destination(&line) {
+                        destinations.push(dest);
+                    }
+                    if let Some(path) = subtitles::parse_subtitle_file(&line) {
+                        subtitle_files.push(path);
+                    }
+                    if let Some(output) = parse_output(&line) {
+                        let _ = tx.send(DownloadEvent::Output(output));
+                    }
+                    let event = match ProgressEvent::parse(&line) {
+                        Some(p) => DownloadEvent::Progress(p),
+                        None => DownloadEvent::Log(line),
+                    };
+                    let _ = tx.send(event);
+                }
+                request = cancelled(&mut cancel) => {
+                    return Err(stop(&mut child, request, &destinations).await);
+                }
+            }
+        }
+
+        tokio::select! {
+            status = child.wait() => status.map_err(|e| DownloadError::Spawn(e.to_string())),
+            request = cancelled(&mut cancel) => Err(stop(&mut child, request, &destinations).await),
+        }
+    }
+    .await;
+
+    // The reader ends with the process; wait for it on every path so its
+    // last lines go out before `Finished`.
+    let stderr: Vec<String> = stderr.await.unwrap_or_default().into();
+    let status = exit?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(DownloadError::from_stderr(&stderr, status.code()))
+    }
+}
+
+/// Writes transcripts for the subtitle files a run produced, then removes the
+/// files themselves unless they were asked for.
+fn finish_subtitles(options: &SubtitleOptions, files: &[PathBuf], tx: &mpsc::UnboundedSender<DownloadEvent>) {
+    for path in files.iter().filter_map(|f| subtitles::converted(f, options.format)) {
+        if options.transcript {
+            let line = match subtitles::write_transcript(&path) {
+                Ok(out) => format!("Transcript saved to {}", out.display()),
+                Err(e) => format!("Could not write a transcript for {}: {}", path.display(), e),
+            };
+            let _ = tx.send(DownloadEvent::Log(line));
+        }
+        if !options.sidecar {
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+}
+
+/// Lines kept for classifying a failure; the full output goes out as events.
+const STDERR_TAIL: usize = 200;
+
+async fn drain_stderr(
+    stderr: tokio::process::ChildStderr,
+    tx: mpsc::UnboundedSender<DownloadEvent>,
+) -> VecDeque<String> {
+    let mut tail = VecDeque::new();
+    let mut reader = BufReader::new(stderr).lines();
+
+    while let Ok(Some(line)) = reader.next_line().await {
+        if tail.len() == STDERR_TAIL {
+            tail.pop_front();
+        }
+        tail.push_back(line.clone());
+        let _ = tx.send(DownloadEvent::Stderr(line));
+    }
+    tail
+}
+
+/// Runs a short-lived command to completion and returns its stdout.
+async fn capture(cmd: &mut Command) -> Result<String, DownloadError> {
+    let output = cmd
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| DownloadError::Spawn(e.to_string()))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let lines: Vec<&str> = stderr.lines().collect();
+        Err(DownloadError::from_stderr(&lines, output.status.code()))
+    }
+}
+
+/// Resolves once a cancel has been requested.
+async fn cancelled(rx: &mut watch::Receiver<Option<CancelRequest>>) -> CancelRequest {
+    loop {
+        if let Some(request) = *rx.borrow_and_update() {
+            return request;
+        }
+        if rx.changed().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+async fn stop(child: &mut Child, request: CancelRequest, destinations: &[PathBuf]) -> DownloadError {
+    process::terminate(child).await;
+    if request.delete_partials {
+        process::remove_partials(destinations);
+    }
+    DownloadError::Cancelled
+}
+
+fn parse_destination(line: &str) -> Option<PathBuf> {
+    line.strip_prefix("[download] Destination: ").map(PathBuf::from)
+}
+
+fn parse_output(line: &str) -> Option<PathBuf> {
+    if let Some(rest) = line.strip_prefix("[Merger] Merging formats into ") {
+        return Some(PathBuf::from(rest.trim_matches('"')));
+    }
+    if let Some(rest) = line.strip_prefix("[download] ")
+        && let Some(path) = rest.strip_suffix(" has already been downloaded")
+    {
+        return Some(PathBuf::from(path));
+    }
+    line.strip_prefix("[ExtractAudio] Destination: ")
+        .map(PathBuf::from)
+        .or_else(|| parse_destination(line))
+}
@@ -0,0 +1,33 @@
+// Post-processing that writes cover art, tags and chapters into the output
+// file, so media libraries show more than a bare file name
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbedOptions {
+    /// Thumbnail as cover art.
+    pub thumbnail: bool,
+    /// Title, artist, date and description tags.
+    pub metadata: bool,
+    /// Chapter markers from the video description or YouTube chapters.
+    pub chapters: bool,
+}
+
+impl EmbedOptions {
+    /// yt-dlp arguments for these options.
+    pub fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.thumbnail {
+            // YouTube serves WebP, which most players ignore as cover art.
+            args.extend(["--embed-thumbnail", "--convert-thumbnails", "jpg"]);
+        }
+        if self.metadata {
+            args.push("--embed-metadata");
+        }
+        if self.chapters {
+            args.push("--embed-chapters");
+        }
+        args.into_iter().map(String::from).collect()
+    }
+}
@@ -0,0 +1,207 @@
+// Download failures, classified from what yt-dlp wrote to stderr
+
+use std::fmt;
+
+/// Known reasons a download fails, each with a hint for the user.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailureKind {
+    PrivateVideo,
+    AgeRestricted,
+    /// Members-only, paid, or behind a sign-in or bot check.
+    LoginRequired,
+    GeoBlocked,
+    RateLimited,
+    FfmpegMissing,
+    UnsupportedUrl,
+    DiskFull,
+    Unknown,
+}
+
+// Checked in order; the first pattern found anywhere in stderr wins.
+const PATTERNS: &[(FailureKind, &str)] = &[
+    (FailureKind::DiskFull, "no space left on device"),
+    (FailureKind::DiskFull, "errno 28"),
+    (FailureKind::FfmpegMissing, "ffmpeg is not installed"),
+    (FailureKind::FfmpegMissing, "ffmpeg not found"),
+    (FailureKind::FfmpegMissing, "ffprobe and ffmpeg not found"),
+    (FailureKind::RateLimited, "http error 429"),
+    (FailureKind::RateLimited, "too many requests"),
+    (FailureKind::PrivateVideo, "private video"),
+    (FailureKind::PrivateVideo, "video is private"),
+    (FailureKind::AgeRestricted, "confirm your age"),
+    (FailureKind::AgeRestricted, "age-restricted"),
+    (FailureKind::AgeRestricted, "inappropriate for some users"),
+    (FailureKind::LoginRequired, "members-only"),
+    (FailureKind::LoginRequired, "available to this channel's members"),
+    (FailureKind::LoginRequired, "join this channel"),
+    (FailureKind::LoginRequired, "requires payment"),
+    (FailureKind::LoginRequired, "cookies are no longer valid"),
+    (FailureKind::LoginRequired, "sign in to confirm"),
+    (FailureKind::LoginRequired, "login required"),
+    (FailureKind::LoginRequired, "use --cookies"),
+    (FailureKind::GeoBlocked, "not available in your country"),
+    (FailureKind::GeoBlocked, "geo restriction"),
+    (FailureKind::GeoBlocked, "geo-restricted"),
+    (FailureKind::UnsupportedUrl, "unsupported url"),
+    (FailureKind::UnsupportedUrl, "is not a valid url"),
+];
+
+impl FailureKind {
+    pub fn classify<S: AsRef<str>>(stderr: &[S]) -> Self {
+        let text = stderr
+            .iter()
+            .map(|l| l.as_ref().to_lowercase())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        PATTERNS
+            .iter()
+            .find(|(_, pattern)| text.contains(pattern))
+            .map(|(kind, _)| *kind)
+            .unwrap_or(FailureKind::Unknown)
+    }
+
+    /// Failures that cookies from the right account could fix.
+    pub fn is_auth(&self) -> bool {
+        matches!(self, FailureKind::PrivateVideo | FailureKind::AgeRestricted | FailureKind::LoginRequired)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FailureKind::PrivateVideo => "Private video",
+            FailureKind::AgeRestricted => "Age-restricted",
+            FailureKind::LoginRequired => "Sign-in required",
+            FailureKind::GeoBlocked => "Geo-blocked",
+            FailureKind::RateLimited => "Rate limited",
+            FailureKind::FfmpegMissing => "ffmpeg missing",
+            FailureKind::UnsupportedUrl => "Unsupported URL",
+            FailureKind::DiskFull => "Disk full",
+            FailureKind::Unknown => "Download failed",
+        }
+    }
+
+    pub fn hint(&self) -> &'static str {
+        match self {
+            FailureKind::PrivateVideo => "The uploader made this video private. Only accounts they shared it with can download it.",
+            FailureKind::AgeRestricted => "YouTube wants a signed-in account for this video. Import cookies.txt from a logged-in browser under Authentication.",
+            FailureKind::LoginRequired => "This video needs an account that can watch it, such as a channel member. Import fresh cookies.txt from that account under Authentication.",
+            FailureKind::GeoBlocked => "This video is not available from your location. A proxy or VPN in another country may work.",
+            FailureKind::RateLimited => "The site is throttling requests (HTTP 429). Wait a while, or lower the number of parallel downloads.",
+            FailureKind::FfmpegMissing => "Merging streams and converting audio need ffmpeg. Install it, or choose where it is under Tools.",
+            FailureKind::UnsupportedUrl => "yt-dlp doesn't recognise this link. Check the URL, or update yt-dlp.",
+            FailureKind::DiskFull => "The output drive is full. Free some space or choose another folder.",
+            FailureKind::Unknown => "See the job log for yt-dlp's output.",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum DownloadError {
+    /// yt-dlp could not be started at all.
+    Spawn(String),
+    /// yt-dlp ran but exited unsuccessfully.
+    Failed {
+        kind: FailureKind,
+        exit_code: Option<i32>,
+        /// Last `ERROR:` line yt-dlp printed.
+        message: Option<String>,
+    },
+    /// yt-dlp succeeded but printed something rstube couldn't parse.
+    Unreadable(String),
+    /// Stopped through a `Canceller`.
+    Cancelled,
+}
+
+impl DownloadError {
+    /// Builds a `Failed` error from the stderr of an unsuccessful run.
+    pub fn from_stderr<S: AsRef<str>>(stderr: &[S], exit_code: Option<i32>) -> Self {
+        let message = stderr
+            .iter()
+            .rev()
+            .find_map(|l| l.as_ref().strip_prefix("ERROR: "))
+            .map(str::to_string);
+
+        DownloadError::Failed {
+            kind: FailureKind::classify(stderr),
+            exit_code,
+            message,
+        }
+    }
+
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            DownloadError::Spawn(_) => Some("Install yt-dlp, or choose where it is under Tools."),
+            DownloadError::Failed { kind, .. } => Some(kind.hint()),
+            DownloadError::Unreadable(_) => Some("Your yt-dlp may be too old or too new for this version of rstube."),
+            DownloadError::Cancelled => None,
+        }
+    }
+
+    pub fn exit_code(&self) -> Option<i32> {
+        match self {
+            DownloadError::Failed { exit_code, .. } => *exit_code,
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DownloadError::Spawn(e) => write!(f, "Failed to start yt-dlp: {}", e),
+            DownloadError::Failed { kind, message: Some(m), .. } => write!(f, "{}: {}", kind.label(), m),
+            DownloadError::Failed { kind, exit_code: Some(code), .. } => {
+                write!(f, "{} (yt-dlp exited with code {})", kind.label(), code)
+            }
+            DownloadError::Failed { kind, .. } => write!(f, "{} (yt-dlp was terminated)", kind.label()),
+            DownloadError::Unreadable(e) => write!(f, "Unexpected yt-dlp output: {}", e),
+            DownloadError::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for DownloadError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_yt_dlp_errors() {
+        let cases = [
+            ("ERROR: [youtube] abc: Private video. Sign in if you've been granted access", FailureKind::PrivateVideo),
+            ("ERROR: [youtube] abc: Sign in to confirm your age", FailureKind::AgeRestricted),
+            ("ERROR: [youtube] abc: Join this channel to get access to members-only content", FailureKind::LoginRequired),
+            ("ERROR: [youtube] abc: Sign in to confirm you're not a bot. Use --cookies", FailureKind::LoginRequired),
+            ("ERROR: [youtube] abc: Video unavailable. This video is not available in your country", FailureKind::GeoBlocked),
+            ("ERROR: unable to download video data: HTTP Error 429: Too Many Requests", FailureKind::RateLimited),
+            ("ERROR: You have requested merging of multiple formats but ffmpeg is not installed", FailureKind::FfmpegMissing),
+            ("ERROR: Unsupported URL: https://example.com/", FailureKind::UnsupportedUrl),
+            ("ERROR: unable to write data: [Errno 28] No space left on device", FailureKind::DiskFull),
+            ("ERROR: something nobody has seen before", FailureKind::Unknown),
+        ];
+        for (line, kind) in cases {
+            assert_eq!(FailureKind::classify(&[line]), kind, "{}", line);
+        }
+    }
+
+    #[test]
+    fn earlier_patterns_win() {
+        let stderr = ["WARNING: HTTP Error 429: Too Many Requests", "ERROR: No space left on device"];
+        assert_eq!(FailureKind::classify(&stderr), FailureKind::DiskFull);
+        assert_eq!(FailureKind::classify::<&str>(&[]), FailureKind::Unknown);
+    }
+
+    #[test]
+    fn keeps_the_last_error_line() {
+        let stderr = ["ERROR: first", "WARNING: noise", "ERROR: Private video"];
+        match DownloadError::from_stderr(&stderr, Some(1)) {
+            DownloadError::Failed { kind, exit_code, message } => {
+                assert_eq!(kind, FailureKind::PrivateVideo);
+                assert_eq!(exit_code, Some(1));
+                assert_eq!(message.as_deref(), Some("Private video"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
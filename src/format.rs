@@ -0,0 +1,91 @@
+// Output formats and the yt-dlp arguments they map to
+
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::audio::AudioOptions;
+use crate::video::VideoOptions;
+
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum Format {
+    BestVideo,
+    AudioOnly,
+    /// Specific format ids picked from the video's format list.
+    /// Either side may be empty; both set means yt-dlp merges them.
+    Custom {
+        video: Option<String>,
+        audio: Option<String>,
+    },
+}
+
+impl Format {
+    /// Short label used in history and status lines.
+    pub fn label(&self) -> String {
+        match self {
+            Format::BestVideo => "Video".into(),
+            Format::AudioOnly => "Audio".into(),
+            Format::Custom { .. } => format!("Format {}", self.selector().unwrap_or_default()),
+        }
+    }
+
+    /// True when the result is an audio file with no video stream.
+    pub fn is_audio_only(&self) -> bool {
+        matches!(self, Format::AudioOnly | Format::Custom { video: None, audio: Some(_) })
+    }
+
+    /// Value for yt-dlp's `-f`, if this format picks streams itself.
+    pub fn selector(&self) -> Option<String> {
+        match self {
+            Format::BestVideo => Some("bestvideo+bestaudio/best".into()),
+            Format::AudioOnly => None,
+            Format::Custom { video, audio } => match (video, audio) {
+                (Some(v), Some(a)) => Some(format!("{}+{}", v, a)),
+                (Some(id), None) | (None, Some(id)) => Some(id.clone()),
+                (None, None) => None,
+            },
+        }
+    }
+
+    /// yt-dlp arguments selecting and post-processing this format.
+    /// `video` and `audio` say how `BestVideo` and `AudioOnly` are fetched.
+    pub fn args(&self, video: &VideoOptions, audio: &AudioOptions) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(selector) = self.selector() {
+            args.extend(["-f".to_string(), selector]);
+        }
+        match self {
+            Format::BestVideo => args.extend(video.args()),
+            Format::AudioOnly => args.extend(audio.args()),
+            Format::Custom { .. } => {}
+        }
+        args
+    }
+}
+
+/// Parses `video`, `audio`, or a yt-dlp format id pair such as `137+140`.
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "" => Err("empty format".into()),
+            "video" | "best" => Ok(Format::BestVideo),
+            "audio" | "mp3" => Ok(Format::AudioOnly),
+            _ => {
+                let (video, audio) = match s.trim().split_once('+') {
+                    Some((v, a)) => (v.trim(), Some(a.trim())),
+                    None => (s.trim(), None),
+                };
+                // Both sides of `137+140` must be a format id.
+                let valid = |id: &str| !id.is_empty() && !id.contains(|c: char| c.is_whitespace() || c == '+');
+                if !valid(video) || !audio.is_none_or(valid) {
+                    return Err(format!("unknown format '{}'", s));
+                }
+                Ok(Format::Custom {
+                    video: Some(video.to_string()),
+                    audio: audio.map(str::to_string),
+                })
+            }
+        }
+    }
+}
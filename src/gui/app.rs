@@ -0,0 +1,1357 @@
+// Main window: URL entry with preview, format choice, queue and history
+
+use eframe::{egui, App};
+use rstube::cookies::{self, DomainCookies};
+use rstube::sections::{self, Section};
+use rstube::settings::DEFAULT_FILENAME_TEMPLATE;
+use rstube::sponsorblock::{self, Category, Segment, SponsorBlockMode};
+use rstube::subtitles::SubtitleFormat;
+use rstube::template::{self, PlaylistPosition};
+use rstube::tools::Tool;
+use rstube::units::{human_bytes, human_duration};
+use rstube::{
+    AudioCodec, AudioOptions, Backends, CancelRequest, CodecOrder, Container, DownloadError, DownloadJob, Downloader,
+    Format, History, ImportReport, IpVersion, JobState, Media, Playlist, Queue, Settings, SponsorBlockOptions,
+    Toolchain, VideoInfo, VideoOptions,
+};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::runtime::Runtime;
+
+use super::clipboard::ClipboardWatcher;
+use super::formats::FormatTable;
+
+/// How long to wait before trying a failed settings save again.
+const SAVE_RETRY: Duration = Duration::from_secs(5);
+
+/// Metadata preview for the URL field, tagged with the URL it belongs to.
+enum Preview {
+    Empty,
+    Loading(String),
+    Video(String, Box<VideoInfo>),
+    /// Entries plus which of them are ticked for download.
+    Playlist(String, Playlist, Vec<bool>),
+    Failed(String, DownloadError),
+}
+
+impl Preview {
+    fn url(&self) -> Option<&str> {
+        match self {
+            Preview::Empty => None,
+            Preview::Loading(url)
+            | Preview::Video(url, _)
+            | Preview::Playlist(url, _, _)
+            | Preview::Failed(url, _) => Some(url),
+        }
+    }
+}
+
+/// Part of a single video to download instead of all of it.
+#[derive(Default)]
+struct Clip {
+    start: String,
+    end: String,
+    /// Titles of the ticked chapters.
+    chapters: Vec<String>,
+}
+
+impl Clip {
+    fn sections(&self) -> Result<Vec<Section>, String> {
+        let mut out = Vec::new();
+        if !self.start.trim().is_empty() || !self.end.trim().is_empty() {
+            let time = |text: &str| sections::parse_time(text).ok_or_else(|| format!("'{}' is not a time", text.trim()));
+            let start = if self.start.trim().is_empty() { 0.0 } else { time(&self.start)? };
+            let end = if self.end.trim().is_empty() { None } else { Some(time(&self.end)?) };
+            out.push(sections::range(start, end)?);
+        }
+        out.extend(self.chapters.iter().cloned().map(Section::Chapter));
+        Ok(out)
+    }
+}
+
+/// SponsorBlock segments of the previewed video, for the options they were
+/// looked up with. `None` while the request is in flight.
+struct SegmentLookup {
+    video_id: String,
+    options: SponsorBlockOptions,
+    result: Option<Result<Vec<Segment>, String>>,
+}
+
+pub struct DownloaderApp {
+    url: String,
+    /// Time range and chapters for the URL field; reset when it changes.
+    clip: Clip,
+    /// Current choice; picked format ids are not saved as the default.
+    format: Format,
+    settings: Settings,
+    /// What is on disk, to spot changes worth saving.
+    saved_settings: Settings,
+    /// When the last save failed; it is tried again a little later.
+    save_failed: Option<Instant>,
+    /// Why the config file couldn't be loaded. Nothing is saved while this
+    /// is set, so a file from a newer version or with a typo isn't replaced
+    /// by defaults.
+    settings_error: Option<String>,
+    /// Inner window size, saved on exit.
+    window_size: Option<[f32; 2]>,
+    preview: Arc<Mutex<Preview>>,
+    segments: Arc<Mutex<Option<SegmentLookup>>>,
+    /// yt-dlp, ffmpeg and ffprobe; `None` while they are being looked for.
+    tools: Arc<Mutex<Option<Toolchain>>>,
+    format_table: FormatTable,
+    /// Outcome of the last "Import list…", until dismissed.
+    import_report: Option<Result<ImportReport, String>>,
+    /// Name for the next imported cookies.txt.
+    cookie_name: String,
+    /// Outcome of the last cookies.txt import, until dismissed.
+    cookie_message: Option<Result<String, String>>,
+    /// What each profile's file covers, read once per file.
+    cookie_summaries: HashMap<PathBuf, Result<Vec<DomainCookies>, String>>,
+
+    /// Running only while "Watch clipboard" is ticked.
+    clipboard: Option<ClipboardWatcher>,
+    clipboard_error: Option<String>,
+    /// Copied links waiting for "Add to queue?".
+    clipboard_offers: Vec<String>,
+    /// Links already offered or queued from the clipboard.
+    clipboard_seen: HashSet<String>,
+
+    /// yt-dlp, for pointing it at the tools found.
+    downloader: Downloader,
+    backends: Backends,
+    queue: Queue,
+    rt: Runtime,
+}
+
+impl DownloaderApp {
+    pub fn new(settings: Settings, settings_error: Option<String>) -> Self {
+        let rt = Runtime::new().expect("Tokio runtime");
+        let history = match History::default_path().map(History::open) {
+            Some(Ok(history)) => history,
+            Some(Err(e)) => {
+                eprintln!("Could not load history: {}", e);
+                History::in_memory()
+            }
+            None => History::in_memory(),
+        };
+        // Found before the saved queue is restored, so its jobs start with the
+        // right yt-dlp and ffmpeg, or are held back if they are missing.
+        let found = rt.block_on(Toolchain::detect(&settings.tools));
+        let downloader = Downloader::default()
+            .with_toolchain(&found)
+            .with_network(settings.network.clone());
+        let backends = Backends::standard(downloader.clone());
+        let queue = Queue::new(backends.clone(), history, rt.handle().clone(), settings.concurrency);
+        if let Some(path) = Queue::default_store_path()
+            && let Err(e) = queue.persist_to(&path)
+        {
+            eprintln!("Could not load saved queue from {}: {}", path.display(), e);
+        }
+
+        let mut app = Self {
+            url: String::new(),
+            clip: Clip::default(),
+            format: settings.format.clone(),
+            settings: settings.clone(),
+            saved_settings: settings,
+            save_failed: None,
+            settings_error,
+            window_size: None,
+            preview: Arc::new(Mutex::new(Preview::Empty)),
+            segments: Arc::new(Mutex::new(None)),
+            tools: Arc::new(Mutex::new(Some(found))),
+            format_table: FormatTable::default(),
+            import_report: None,
+            cookie_name: String::new(),
+            cookie_message: None,
+            cookie_summaries: HashMap::new(),
+            clipboard: None,
+            clipboard_error: None,
+            clipboard_offers: Vec::new(),
+            clipboard_seen: HashSet::new(),
+            downloader,
+            backends,
+            queue,
+            rt,
+        };
+        if app.settings.watch_clipboard {
+            app.set_clipboard_watch(true);
+        }
+        app
+    }
+
+    /// Writes settings to disk if anything changed since the last save.
+    fn save_settings(&mut self) {
+        if self.settings == self.saved_settings || self.settings_error.is_some() {
+            return;
+        }
+        if self.save_failed.is_some_and(|t| t.elapsed() < SAVE_RETRY) {
+            return;
+        }
+        let Some(path) = Settings::default_path() else {
+            return;
+        };
+        match self.settings.save(&path) {
+            Ok(()) => {
+                self.saved_settings = self.settings.clone();
+                self.save_failed = None;
+            }
+            Err(e) => {
+                eprintln!("Could not save settings to {}: {}", path.display(), e);
+                self.save_failed = Some(Instant::now());
+            }
+        }
+    }
+}
+
+impl App for DownloaderApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll_clipboard();
+        if let Some(rect) = ctx.input(|i| i.viewport().inner_rect) {
+            self.window_size = Some([rect.width(), rect.height()]);
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("🎬 Rust YouTube Downloader — v1.0");
+            ui.separator();
+            egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+                if let Some(e) = &self.settings_error {
+                    ui.colored_label(egui::Color32::RED, format!("❌ {}", e));
+                    ui.label(
+                        "Using default settings for now. Changes won't be saved until the file is fixed or removed.",
+                    );
+                    ui.separator();
+                }
+
+                self.clipboard_ui(ui);
+
+                ui.label("YouTube URL");
+                ui.horizontal(|ui| {
+                    let edit = ui.text_edit_singleline(&mut self.url);
+                    if edit.changed() {
+                        self.clip = Clip::default();
+                        if let Some(t) = sections::start_time(&self.url) {
+                            self.clip.start = sections::clock(t);
+                        }
+                    }
+                    let fetch = ui.button("🔍 Preview").clicked() || edit.lost_focus();
+                    let stale = self.preview.lock().unwrap().url() != Some(self.url.as_str());
+                    if fetch && stale && !self.url.is_empty() {
+                        self.fetch_preview();
+                    }
+                });
+                self.preview_ui(ui);
+                self.clip_ui(ui);
+
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Format:");
+                    ui.radio_value(&mut self.format, Format::BestVideo, "Best Video");
+                    ui.radio_value(&mut self.format, Format::AudioOnly, "Audio");
+                    if let Format::Custom { .. } = &self.format {
+                        let _ = ui.radio(true, self.format.label());
+                    }
+                    match self.format {
+                        Format::BestVideo => self.video_ui(ui),
+                        Format::AudioOnly => self.audio_ui(ui),
+                        Format::Custom { .. } => {}
+                    }
+                    ui.separator();
+                    let embed = self.settings.embed_for_mut(&self.format);
+                    ui.checkbox(&mut embed.thumbnail, "🖼 Cover art")
+                        .on_hover_text("Embed the thumbnail as cover art");
+                    ui.checkbox(&mut embed.metadata, "🏷 Tags")
+                        .on_hover_text("Write title, artist, date and description tags");
+                    ui.checkbox(&mut embed.chapters, "📑 Chapters")
+                        .on_hover_text("Embed chapter markers");
+                });
+                self.sponsorblock_ui(ui);
+                self.subtitles_ui(ui);
+                self.auth_ui(ui);
+                self.network_ui(ui);
+                self.tools_ui(ui);
+
+                if ui.button("📁 Choose Folder").clicked()
+                    && let Some(path) = rfd::FileDialog::new().pick_folder()
+                {
+                    self.settings.output_dir = Some(path);
+                }
+
+                if let Some(dir) = &self.settings.output_dir {
+                    ui.label(format!("Saving to: {}", dir.display()));
+                }
+                self.settings_ui(ui);
+
+                // Wait for the preview so a playlist is queued as its entries, not as one job.
+                let (label, loading) = match &*self.preview.lock().unwrap() {
+                    Preview::Playlist(url, _, selected) if *url == self.url => {
+                        (format!("⬇ Download {} selected", selected.iter().filter(|s| **s).count()), false)
+                    }
+                    Preview::Loading(url) => ("⬇ Download".into(), *url == self.url),
+                    _ => ("⬇ Download".into(), false),
+                };
+                let blocked = self.blocked_reason();
+                ui.horizontal(|ui| {
+                    let clip_ok = self.clip.sections().is_ok();
+                    let button = ui.add_enabled(clip_ok && !loading && blocked.is_none(), egui::Button::new(label));
+                    if button.clicked() && !self.url.is_empty() {
+                        self.enqueue();
+                    }
+                    if let Some(reason) = &blocked {
+                        ui.colored_label(egui::Color32::RED, reason);
+                    }
+                    if ui.button("📄 Import list…").clicked()
+                        && let Some(path) = rfd::FileDialog::new()
+                            .add_filter("URL list", &["txt", "csv"])
+                            .pick_file()
+                    {
+                        self.import(&path);
+                    }
+                });
+                self.import_ui(ui);
+
+                ui.separator();
+                self.jobs_ui(ui);
+
+                ui.separator();
+                ui.heading("📜 History");
+                for item in self.queue.history().items().iter().rev() {
+                    let started = item.started_at.with_timezone(&chrono::Local);
+                    let mut line = format!(
+                        "{} | {} | {} | {}",
+                        started.format("%Y-%m-%d %H:%M"),
+                        item.display_name(),
+                        item.format,
+                        item.status
+                    );
+                    if let Some(bytes) = item.bytes {
+                        line += &format!(" | {}", human_bytes(bytes));
+                    }
+                    line += &format!(" | {}", human_duration(item.duration().num_seconds().max(0) as u64));
+
+                    if item.auth_failure {
+                        line = format!("🔒 {}", line);
+                    }
+                    if let Some(profile) = &item.cookies {
+                        line += &format!(" | 🔑 {}", profile);
+                    }
+
+                    let label = ui.label(line);
+                    if item.auth_failure {
+                        label.on_hover_text(
+                            "The site wanted a signed-in account. Import cookies.txt from an account that can watch \
+                             this video under Authentication, then try again.",
+                        );
+                    } else if let Some(output) = &item.output {
+                        label.on_hover_text(output.display().to_string());
+                    }
+                }
+            });
+        });
+
+        if !matches!(self.format, Format::Custom { .. }) {
+            self.settings.format = self.format.clone();
+        }
+        // Not while a text field has focus, so typing a template or proxy
+        // doesn't write the file on every keystroke.
+        if ctx.memory(|m| m.focused().is_none()) {
+            self.save_settings();
+        }
+
+        ctx.request_repaint();
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if self.window_size.is_some() {
+            self.settings.window_size = self.window_size;
+        }
+        // Last chance, so don't wait out a failed save.
+        self.save_failed = None;
+        self.save_settings();
+    }
+}
+
+impl DownloaderApp {
+    fn fetch_preview(&mut self) {
+        // Picked format ids only make sense for the video they came from.
+        if let Format::Custom { .. } = self.format {
+            self.format = Format::BestVideo;
+        }
+
+        let url = self.url.clone();
+        *self.preview.lock().unwrap() = Preview::Loading(url.clone());
+
+        // Asked of whichever backend the download itself would go to.
+        let job = self.job(url.clone());
+        let backends = self.backends.clone();
+        let preview = self.preview.clone();
+        self.rt.spawn(async move {
+            let result = backends.pick(&job).inspect(&url).await;
+            let mut preview = preview.lock().unwrap();
+            // Ignore answers for a URL the user has since replaced.
+            if preview.url() == Some(url.as_str()) {
+                *preview = match result {
+                    Ok(Media::Video(info)) => Preview::Video(url, info),
+                    Ok(Media::Playlist(playlist)) => {
+                        let selected = vec![true; playlist.entries.len()];
+                        Preview::Playlist(url, playlist, selected)
+                    }
+                    Err(e) => Preview::Failed(url, e),
+                };
+            }
+        });
+    }
+
+    /// A job for `url` with the current format, output folder and templates.
+    fn job(&self, url: String) -> DownloadJob {
+        self.settings.job(url, self.format.clone())
+    }
+
+    fn poll_clipboard(&mut self) {
+        let Some(watcher) = &self.clipboard else {
+            return;
+        };
+        for url in watcher.drain() {
+            if !self.clipboard_seen.insert(url.clone()) {
+                continue;
+            }
+            if self.settings.collect_mode {
+                self.queue.push(self.job(url));
+            } else {
+                self.clipboard_offers.push(url);
+            }
+        }
+    }
+
+    fn set_clipboard_watch(&mut self, watching: bool) {
+        self.clipboard = None;
+        self.clipboard_error = None;
+        self.settings.watch_clipboard = watching;
+        if watching {
+            match ClipboardWatcher::start() {
+                Ok(watcher) => self.clipboard = Some(watcher),
+                Err(e) => self.clipboard_error = Some(e.to_string()),
+            }
+        }
+    }
+
+    fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("⚙ Settings")
+            .id_source("settings")
+            .show(ui, |ui| {
+                egui::Grid::new("templates").num_columns(2).show(ui, |ui| {
+                    ui.label("File name:");
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut self.settings.filename_template);
+                        if ui.button("Reset").clicked() {
+                            self.settings.filename_template = DEFAULT_FILENAME_TEMPLATE.into();
+                        }
+                    });
+                    ui.end_row();
+                    ui.label("Video folder:");
+                    ui.add(egui::TextEdit::singleline(&mut self.settings.video_folder).hint_text("Videos/"));
+                    ui.end_row();
+                    ui.label("Audio folder:");
+                    ui.add(egui::TextEdit::singleline(&mut self.settings.audio_folder).hint_text("Music/{uploader}/"));
+                    ui.end_row();
+                });
+                let placeholders: Vec<String> = template::PLACEHOLDERS
+                    .iter()
+                    .map(|(name, about)| format!("{{{}}} {}", name, about))
+                    .collect();
+                ui.small(placeholders.join(" · "));
+                self.template_preview_ui(ui);
+                ui.horizontal(|ui| {
+                    ui.label("SponsorBlock server:");
+                    ui.text_edit_singleline(&mut self.settings.sponsorblock.api);
+                    if ui.button("Reset").clicked() {
+                        self.settings.sponsorblock.api = sponsorblock::DEFAULT_API.into();
+                    }
+                });
+                if self.settings.output_dir.is_some() && ui.button("Use yt-dlp's default folder").clicked() {
+                    self.settings.output_dir = None;
+                }
+            });
+    }
+
+    /// Resolution, frame rate, codec, HDR and container pickers for video downloads.
+    fn video_ui(&mut self, ui: &mut egui::Ui) {
+        let video = &mut self.settings.video;
+        let height = |h: Option<u32>| h.map_or("Any resolution".to_string(), |h| format!("≤ {}p", h));
+        let fps = |f: Option<u32>| f.map_or("Any fps".to_string(), |f| format!("≤ {} fps", f));
+
+        egui::ComboBox::from_id_source("video_height")
+            .selected_text(height(video.max_height))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut video.max_height, None, height(None));
+                for h in VideoOptions::HEIGHTS {
+                    ui.selectable_value(&mut video.max_height, Some(h), height(Some(h)));
+                }
+            });
+        egui::ComboBox::from_id_source("video_fps")
+            .selected_text(fps(video.max_fps))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut video.max_fps, None, fps(None));
+                for f in VideoOptions::FPS {
+                    ui.selectable_value(&mut video.max_fps, Some(f), fps(Some(f)));
+                }
+            });
+        egui::ComboBox::from_id_source("video_codec")
+            .selected_text(video.codec_order.label())
+            .show_ui(ui, |ui| {
+                for order in CodecOrder::ALL {
+                    ui.selectable_value(&mut video.codec_order, order, order.label());
+                }
+            });
+        ui.checkbox(&mut video.hdr, "HDR");
+        egui::ComboBox::from_id_source("video_container")
+            .selected_text(video.container.ext())
+            .show_ui(ui, |ui| {
+                for container in Container::ALL {
+                    ui.selectable_value(&mut video.container, container, container.ext());
+                }
+            });
+    }
+
+    /// Codec and bitrate pickers for audio downloads.
+    fn audio_ui(&mut self, ui: &mut egui::Ui) {
+        let audio = &mut self.settings.audio;
+        egui::ComboBox::from_id_source("audio_codec")
+            .selected_text(audio.codec.label())
+            .show_ui(ui, |ui| {
+                for codec in AudioCodec::ALL {
+                    ui.selectable_value(&mut audio.codec, codec, codec.label());
+                }
+            });
+
+        let bitrate_label = |b: Option<u32>| match b {
+            Some(k) => format!("{} kbit/s", k),
+            None => "Best (VBR)".to_string(),
+        };
+        ui.add_enabled_ui(audio.codec.has_bitrate(), |ui| {
+            egui::ComboBox::from_id_source("audio_bitrate")
+                .selected_text(bitrate_label(audio.bitrate))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut audio.bitrate, None, bitrate_label(None));
+                    for k in AudioOptions::BITRATES {
+                        ui.selectable_value(&mut audio.bitrate, Some(k), bitrate_label(Some(k)));
+                    }
+                });
+        });
+    }
+
+    fn sponsorblock_ui(&mut self, ui: &mut egui::Ui) {
+        let sb = &mut self.settings.sponsorblock;
+        ui.horizontal_wrapped(|ui| {
+            ui.label("🚫 SponsorBlock:");
+            for category in Category::ALL {
+                let mut picked = sb.categories.contains(&category);
+                if ui.checkbox(&mut picked, category.label()).changed() {
+                    if picked {
+                        sb.categories.push(category);
+                    } else {
+                        sb.categories.retain(|c| *c != category);
+                    }
+                }
+            }
+            ui.separator();
+            ui.radio_value(&mut sb.mode, SponsorBlockMode::Remove, "Remove");
+            ui.radio_value(&mut sb.mode, SponsorBlockMode::Mark, "Mark as chapters");
+        });
+
+        let video_id = match &*self.preview.lock().unwrap() {
+            Preview::Video(_, info) if sb.is_enabled() => info.id.clone(),
+            _ => return,
+        };
+        let stale = match &*self.segments.lock().unwrap() {
+            Some(lookup) => lookup.video_id != video_id || lookup.options != *sb,
+            None => true,
+        };
+        if stale {
+            self.lookup_segments(video_id);
+        }
+
+        let verb = match self.settings.sponsorblock.mode {
+            SponsorBlockMode::Remove => "removed",
+            SponsorBlockMode::Mark => "marked",
+        };
+        match self.segments.lock().unwrap().as_ref().and_then(|l| l.result.as_ref()) {
+            None => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Looking up segments…");
+                });
+            }
+            Some(Ok(segments)) if segments.is_empty() => {
+                ui.label("No segments submitted for this video.");
+            }
+            Some(Ok(segments)) => {
+                let total: f64 = segments.iter().map(Segment::length).sum();
+                ui.label(format!(
+                    "{} segments, {} will be {}.",
+                    segments.len(),
+                    human_duration(total as u64),
+                    verb
+                ));
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, format!("SponsorBlock lookup failed: {}", e));
+            }
+        }
+    }
+
+    fn lookup_segments(&self, video_id: String) {
+        let options = self.settings.sponsorblock.clone();
+        let network = self.settings.network.clone();
+        *self.segments.lock().unwrap() = Some(SegmentLookup {
+            video_id: video_id.clone(),
+            options: options.clone(),
+            result: None,
+        });
+
+        let segments = self.segments.clone();
+        self.rt.spawn(async move {
+            let result = sponsorblock::segments(&options, &network, &video_id).await;
+            // Ignore answers for options or a video the user has since changed.
+            if let Some(lookup) = &mut *segments.lock().unwrap()
+                && lookup.video_id == video_id
+                && lookup.options == options
+            {
+                lookup.result = Some(result);
+            }
+        });
+    }
+
+    fn subtitles_ui(&mut self, ui: &mut egui::Ui) {
+        let languages = match &*self.preview.lock().unwrap() {
+            Preview::Video(_, info) => info.subtitle_languages(),
+            _ => Vec::new(),
+        };
+        let subs = &mut self.settings.subtitles;
+        let title = match subs.languages.len() {
+            0 => "💬 Subtitles: off".to_string(),
+            _ => format!("💬 Subtitles: {}", subs.languages.join(", ")),
+        };
+
+        egui::CollapsingHeader::new(title).id_source("subtitles").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Languages:");
+                let mut codes = subs.languages.join(", ");
+                let edit = egui::TextEdit::singleline(&mut codes).hint_text("en, de, fr.*");
+                if ui.add(edit).changed() {
+                    subs.languages = codes
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|c| !c.is_empty())
+                        .map(String::from)
+                        .collect();
+                }
+            });
+
+            if !languages.is_empty() {
+                let uploaded = languages.iter().filter(|l| !l.auto).count();
+                ui.label(format!(
+                    "This video has {} uploaded and {} auto-generated languages:",
+                    uploaded,
+                    languages.len() - uploaded
+                ));
+                egui::ScrollArea::vertical()
+                    .id_source("subtitle_languages")
+                    .max_height(120.0)
+                    .show(ui, |ui| {
+                        ui.horizontal_wrapped(|ui| {
+                            for language in &languages {
+                                let mut picked = subs.languages.contains(&language.code);
+                                let mut text = match &language.name {
+                                    Some(name) => format!("{} ({})", name, language.code),
+                                    None => language.code.clone(),
+                                };
+                                if language.auto {
+                                    text += " · auto";
+                                }
+                                if ui.checkbox(&mut picked, text).changed() {
+                                    if picked {
+                                        subs.languages.push(language.code.clone());
+                                    } else {
+                                        subs.languages.retain(|c| *c != language.code);
+                                    }
+                                }
+                            }
+                        });
+                    });
+            }
+
+            ui.checkbox(&mut subs.auto_generated, "Use auto-generated captions when there are no uploaded ones");
+            ui.horizontal(|ui| {
+                ui.label("Format:");
+                ui.radio_value(&mut subs.format, SubtitleFormat::Srt, "SRT");
+                ui.radio_value(&mut subs.format, SubtitleFormat::Vtt, "VTT");
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut subs.sidecar, "Save as file");
+                ui.checkbox(&mut subs.embed, "Embed in video");
+                ui.checkbox(&mut subs.transcript, "Export transcript (.txt)");
+            });
+        });
+    }
+
+    /// Cookie profiles: import a cookies.txt, see what each covers, pick the
+    /// preferred one and remove old ones.
+    fn auth_ui(&mut self, ui: &mut egui::Ui) {
+        let title = match (&self.settings.active_cookies, self.settings.cookie_profiles.len()) {
+            (_, 0) => "🔑 Authentication: none".to_string(),
+            (Some(name), _) => format!("🔑 Authentication: {}", name),
+            (None, n) => format!("🔑 Authentication: {} profiles", n),
+        };
+
+        egui::CollapsingHeader::new(title).id_source("auth").show(ui, |ui| {
+            ui.small(
+                "Export cookies.txt from a browser signed in to the site (Netscape format) to download \
+                 age-restricted or members-only videos. Each link uses the profile that covers its site.",
+            );
+            ui.horizontal(|ui| {
+                ui.label("Profile name:");
+                ui.add(egui::TextEdit::singleline(&mut self.cookie_name).hint_text("YouTube"));
+                let named = !self.cookie_name.trim().is_empty();
+                if ui.add_enabled(named, egui::Button::new("📥 Import cookies.txt…")).clicked()
+                    && let Some(path) = rfd::FileDialog::new()
+                        .add_filter("Netscape cookies", &["txt"])
+                        .pick_file()
+                {
+                    self.import_cookies(&path);
+                }
+            });
+
+            let mut dismiss = false;
+            match &self.cookie_message {
+                None => {}
+                Some(Ok(m)) => {
+                    ui.horizontal(|ui| {
+                        ui.label(m);
+                        dismiss = ui.small_button("✖").clicked();
+                    });
+                }
+                Some(Err(e)) => {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(egui::Color32::RED, format!("❌ {}", e));
+                        dismiss = ui.small_button("✖").clicked();
+                    });
+                }
+            }
+            if dismiss {
+                self.cookie_message = None;
+            }
+
+            let mut remove = None;
+            for (i, profile) in self.settings.cookie_profiles.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let active = self.settings.active_cookies.as_ref() == Some(&profile.name);
+                    if ui
+                        .radio(active, &profile.name)
+                        .on_hover_text("Prefer this profile where several cover the same site")
+                        .clicked()
+                    {
+                        self.settings.active_cookies = (!active).then(|| profile.name.clone());
+                    }
+                    if ui.small_button("🗑").on_hover_text("Remove profile and its cookie file").clicked() {
+                        remove = Some(i);
+                    }
+                });
+
+                let summary = self
+                    .cookie_summaries
+                    .entry(profile.file.clone())
+                    .or_insert_with(|| cookies::summarize(&profile.file).map_err(|e| e.to_string()));
+                ui.indent(&profile.name, |ui| match summary {
+                    Err(e) => {
+                        ui.colored_label(egui::Color32::RED, format!("Could not read {}: {}", profile.file.display(), e));
+                    }
+                    Ok(domains) => {
+                        for d in domains.iter() {
+                            let expiry = match d.expires {
+                                None => "session only".to_string(),
+                                Some(e) => {
+                                    let verb = if d.is_expired() { "expired" } else { "expires" };
+                                    format!("{} {}", verb, e.with_timezone(&chrono::Local).format("%Y-%m-%d"))
+                                }
+                            };
+                            let text = format!("{} — {} cookies, {}", d.domain, d.count, expiry);
+                            if d.is_expired() {
+                                ui.colored_label(egui::Color32::RED, text);
+                            } else {
+                                ui.label(text);
+                            }
+                        }
+                    }
+                });
+            }
+            if let Some(i) = remove {
+                let profile = self.settings.cookie_profiles.remove(i);
+                if self.settings.active_cookies.as_ref() == Some(&profile.name) {
+                    self.settings.active_cookies = None;
+                }
+                self.cookie_summaries.remove(&profile.file);
+                if let Err(e) = std::fs::remove_file(&profile.file)
+                    && e.kind() != std::io::ErrorKind::NotFound
+                {
+                    self.cookie_message = Some(Err(format!("Could not delete {}: {}", profile.file.display(), e)));
+                }
+            }
+        });
+    }
+
+    /// Proxy, bandwidth cap, retries, pauses and local address. Valid changes
+    /// apply to every yt-dlp run started afterwards, queued jobs included.
+    fn network_ui(&mut self, ui: &mut egui::Ui) {
+        let net = &mut self.settings.network;
+        let mut title = "🌐 Network".to_string();
+        if !net.proxy.trim().is_empty() {
+            title += " · proxy";
+        }
+        if let Some(k) = net.rate_limit {
+            title += &format!(" · ≤ {}/s", human_bytes(k as u64 * 1024));
+        }
+
+        egui::CollapsingHeader::new(title).id_source("network").show(ui, |ui| {
+            egui::Grid::new("network_grid").num_columns(2).show(ui, |ui| {
+                ui.label("Proxy:");
+                ui.add(egui::TextEdit::singleline(&mut net.proxy).hint_text("socks5://127.0.0.1:1080"));
+                ui.end_row();
+                ui.label("Speed limit:");
+                optional_value(ui, &mut net.rate_limit, 1024, " KiB/s", "Unlimited");
+                ui.end_row();
+                ui.label("Retries:");
+                optional_value(ui, &mut net.retries, 10, "", "yt-dlp default (10)");
+                ui.end_row();
+                ui.label("Fragment retries:");
+                optional_value(ui, &mut net.fragment_retries, 10, "", "yt-dlp default (10)");
+                ui.end_row();
+                ui.label("Pause between requests:");
+                optional_value(ui, &mut net.sleep_requests, 1.0, " s", "None");
+                ui.end_row();
+                ui.label("Pause before each download:");
+                optional_value(ui, &mut net.sleep_interval, 5.0, " s", "None");
+                ui.end_row();
+                ui.label("IP version:");
+                ui.horizontal(|ui| {
+                    for v in IpVersion::ALL {
+                        ui.radio_value(&mut net.ip_version, v, v.label());
+                    }
+                });
+                ui.end_row();
+                ui.label("Source address:");
+                ui.add(egui::TextEdit::singleline(&mut net.source_address).hint_text("Any local address"));
+                ui.end_row();
+            });
+            if let Err(e) = net.validate() {
+                ui.colored_label(egui::Color32::RED, format!("Not applied: {}", e));
+            }
+        });
+
+        if *net != self.downloader.network() && net.validate().is_ok() {
+            self.backends.set_network(net);
+        }
+    }
+
+    /// Looks for yt-dlp and ffmpeg again, and points the downloader at what it finds.
+    fn detect_tools(&self) {
+        *self.tools.lock().unwrap() = None;
+        let paths = self.settings.tools.clone();
+        let tools = self.tools.clone();
+        let downloader = self.downloader.clone();
+        self.rt.spawn(async move {
+            let found = Toolchain::detect(&paths).await;
+            downloader.set_toolchain(&found);
+            *tools.lock().unwrap() = Some(found);
+        });
+    }
+
+    /// Why the Download button is off, e.g. no yt-dlp, or no ffmpeg for a
+    /// download that needs it.
+    fn blocked_reason(&self) -> Option<String> {
+        let job = self.job(self.url.clone()).sections(self.clip.sections().unwrap_or_default());
+        self.backends
+            .pick(&job)
+            .blocked(&job)
+            .map(|e| format!("{}; see 🧰 Tools", e))
+    }
+
+    /// Where yt-dlp, ffmpeg and ffprobe are, their versions, and pickers for
+    /// ones that aren't found on their own.
+    fn tools_ui(&mut self, ui: &mut egui::Ui) {
+        let found = self.tools.lock().unwrap().clone();
+        let title = match &found {
+            None => "🧰 Tools: checking…".to_string(),
+            Some(t) if !t.warnings().is_empty() => "🧰 Tools ⚠".to_string(),
+            Some(_) => "🧰 Tools".to_string(),
+        };
+
+        let mut changed = false;
+        egui::CollapsingHeader::new(title).id_source("tools").show(ui, |ui| {
+            let Some(found) = &found else {
+                ui.spinner();
+                return;
+            };
+            egui::Grid::new("tools_grid").num_columns(4).show(ui, |ui| {
+                for status in found.tools() {
+                    ui.label(status.tool.name());
+                    match &status.version {
+                        Some(v) => ui.label(format!("✔ {}", v)),
+                        None => ui.colored_label(egui::Color32::RED, "✘ missing"),
+                    };
+                    match (&status.path, status.source) {
+                        (Some(path), Some(source)) => ui.label(format!("{} ({})", path.display(), source.label())),
+                        _ => ui.weak("—"),
+                    };
+                    let explicit = match status.tool {
+                        Tool::YtDlp => Some(&mut self.settings.tools.yt_dlp),
+                        Tool::Ffmpeg => Some(&mut self.settings.tools.ffmpeg),
+                        Tool::Ffprobe => None,
+                    };
+                    ui.horizontal(|ui| match explicit {
+                        Some(explicit) => {
+                            if ui.button("Choose…").clicked()
+                                && let Some(path) = rfd::FileDialog::new()
+                                    .set_title(format!("Where is {}?", status.tool.name()))
+                                    .pick_file()
+                            {
+                                *explicit = Some(path);
+                                changed = true;
+                            }
+                            if explicit.is_some() && ui.button("Search").on_hover_text("Look on PATH again").clicked() {
+                                *explicit = None;
+                                changed = true;
+                            }
+                        }
+                        None => {
+                            ui.weak("next to ffmpeg, or on PATH");
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+            for warning in found.warnings() {
+                ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", warning));
+            }
+            if ui.button("🔄 Check again").clicked() {
+                changed = true;
+            }
+        });
+
+        if changed {
+            self.detect_tools();
+        }
+    }
+
+    fn import_cookies(&mut self, path: &std::path::Path) {
+        let Some(dir) = cookies::profiles_dir() else {
+            self.cookie_message = Some(Err("No data directory available on this system.".into()));
+            return;
+        };
+        let name = self.cookie_name.trim().to_string();
+        match cookies::import(path, &name, &dir) {
+            Ok((profile, summary)) => {
+                let expired = summary.iter().filter(|d| d.is_expired()).count();
+                let mut message = format!("Imported {} with cookies for {} domains", name, summary.len());
+                if expired > 0 {
+                    message += &format!(" ({} already expired)", expired);
+                }
+                self.cookie_message = Some(Ok(message));
+                self.cookie_summaries.insert(profile.file.clone(), Ok(summary));
+                self.settings.cookie_profiles.retain(|p| p.name != name);
+                self.settings.cookie_profiles.push(profile);
+                self.cookie_name.clear();
+            }
+            Err(e) => self.cookie_message = Some(Err(format!("Could not import {}: {}", path.display(), e))),
+        }
+    }
+
+    /// Shows where a video and an audio download would be saved, using the
+    /// previewed video's metadata or a made-up sample.
+    fn template_preview_ui(&self, ui: &mut egui::Ui) {
+        let (info, playlist) = match &*self.preview.lock().unwrap() {
+            Preview::Video(_, info) => ((**info).clone(), None),
+            Preview::Playlist(_, playlist, _) if !playlist.entries.is_empty() => {
+                let entry = &playlist.entries[0];
+                let info = VideoInfo {
+                    id: entry.id.clone(),
+                    title: entry.title.clone().unwrap_or_default(),
+                    uploader: playlist.uploader.clone(),
+                    ..VideoInfo::default()
+                };
+                let position = PlaylistPosition {
+                    title: playlist.title.clone(),
+                    index: entry.index,
+                };
+                (info, Some(position))
+            }
+            _ => (sample_info(), None),
+        };
+
+        let video_ext = self.settings.video.container.ext();
+        let audio_ext = self.settings.audio.codec.ext().unwrap_or("opus");
+        for (label, format, ext) in [("Video", Format::BestVideo, video_ext), ("Audio", Format::AudioOnly, audio_ext)] {
+            let Some(template) = self.settings.output_template(&format) else {
+                continue;
+            };
+            match template::render(&template, &info, playlist.as_ref(), ext) {
+                Ok(path) => ui.label(format!("{}: {}", label, path)),
+                Err(e) => ui.colored_label(egui::Color32::RED, format!("{} template: {}", label, e)),
+            };
+        }
+    }
+
+    fn clipboard_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let mut watching = self.clipboard.is_some();
+            if ui.checkbox(&mut watching, "📋 Watch clipboard").changed() {
+                self.set_clipboard_watch(watching);
+            }
+            ui.add_enabled(
+                self.clipboard.is_some(),
+                egui::Checkbox::new(&mut self.settings.collect_mode, "Collect mode"),
+            )
+            .on_hover_text("Add copied links to the queue without asking");
+        });
+        if let Some(e) = &self.clipboard_error {
+            ui.colored_label(egui::Color32::RED, format!("Clipboard unavailable: {}", e));
+        }
+
+        let mut answered = None;
+        for (i, url) in self.clipboard_offers.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!("📋 Add to queue? {}", url));
+                if ui.small_button("➕ Add").clicked() {
+                    answered = Some((i, true));
+                }
+                if ui.small_button("✖").clicked() {
+                    answered = Some((i, false));
+                }
+            });
+        }
+        if let Some((i, add)) = answered {
+            let url = self.clipboard_offers.remove(i);
+            if add {
+                self.queue.push(self.job(url));
+            }
+        }
+    }
+
+    fn import(&mut self, path: &std::path::Path) {
+        let report = match rstube::import::read(path) {
+            Ok(report) => report,
+            Err(e) => {
+                self.import_report = Some(Err(format!("Could not read {}: {}", path.display(), e)));
+                return;
+            }
+        };
+
+        for entry in &report.entries {
+            let mut job = self.job(entry.url.clone());
+            if let Some(format) = &entry.format {
+                job.format = format.clone();
+            }
+            self.queue.push(job);
+        }
+        self.import_report = Some(Ok(report));
+    }
+
+    fn import_ui(&mut self, ui: &mut egui::Ui) {
+        let mut dismiss = false;
+        match &self.import_report {
+            None => {}
+            Some(Err(e)) => {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::RED, format!("❌ {}", e));
+                    dismiss = ui.small_button("✖").clicked();
+                });
+            }
+            Some(Ok(report)) => {
+                ui.horizontal(|ui| {
+                    ui.label(report.summary());
+                    dismiss = ui.small_button("✖").clicked();
+                });
+                if !report.rejected.is_empty() {
+                    egui::CollapsingHeader::new("Rejected lines")
+                        .id_source("import-rejected")
+                        .show(ui, |ui| {
+                            for r in &report.rejected {
+                                ui.label(format!("Line {}: {} — {}", r.line, r.text, r.reason));
+                            }
+                        });
+                }
+            }
+        }
+        if dismiss {
+            self.import_report = None;
+        }
+    }
+
+    /// Queues the URL field: one job per ticked entry for a playlist,
+    /// otherwise a single job.
+    fn enqueue(&self) {
+        let job = |url: String, title: Option<String>| {
+            let job = self.job(url);
+            match title {
+                Some(t) => job.title(t),
+                None => job,
+            }
+        };
+
+        match &*self.preview.lock().unwrap() {
+            Preview::Playlist(url, playlist, selected) if *url == self.url => {
+                for (entry, _) in playlist.entries.iter().zip(selected).filter(|(_, s)| **s) {
+                    let position = PlaylistPosition {
+                        title: playlist.title.clone(),
+                        index: entry.index,
+                    };
+                    self.queue.push(job(entry.video_url(), entry.title.clone()).playlist(position));
+                }
+            }
+            Preview::Video(url, info) if *url == self.url => {
+                let sections = self.clip.sections().unwrap_or_default();
+                self.queue.push(job(self.url.clone(), Some(info.title.clone())).sections(sections));
+            }
+            _ => {
+                let sections = self.clip.sections().unwrap_or_default();
+                self.queue.push(job(self.url.clone(), None).sections(sections));
+            }
+        }
+    }
+
+    /// Start/end fields and a chapter picker for downloading part of a video.
+    fn clip_ui(&mut self, ui: &mut egui::Ui) {
+        let chapters = match &*self.preview.lock().unwrap() {
+            Preview::Playlist(..) => return,
+            Preview::Video(_, info) => info.chapters.clone().unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        let clip = &mut self.clip;
+        let title = match clip.sections() {
+            Ok(sections) if !sections.is_empty() => {
+                let parts: Vec<String> = sections.iter().map(Section::to_string).collect();
+                format!("✂ Part of the video: {}", parts.join(", "))
+            }
+            _ => "✂ Part of the video".to_string(),
+        };
+
+        egui::CollapsingHeader::new(title).id_source("clip").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("From");
+                ui.add(egui::TextEdit::singleline(&mut clip.start).hint_text("0:00").desired_width(80.0));
+                ui.label("to");
+                ui.add(egui::TextEdit::singleline(&mut clip.end).hint_text("end").desired_width(80.0));
+                if ui.button("Clear").clicked() {
+                    *clip = Clip::default();
+                }
+            });
+            if let Err(e) = clip.sections() {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+
+            if !chapters.is_empty() {
+                ui.label("Chapters:");
+                egui::ScrollArea::vertical()
+                    .id_source("chapters")
+                    .max_height(160.0)
+                    .show(ui, |ui| {
+                        for chapter in &chapters {
+                            let mut picked = clip.chapters.contains(&chapter.title);
+                            let text = format!(
+                                "{}–{} {}",
+                                sections::clock(chapter.start_time),
+                                sections::clock(chapter.end_time),
+                                chapter.title
+                            );
+                            if ui.checkbox(&mut picked, text).changed() {
+                                if picked {
+                                    clip.chapters.push(chapter.title.clone());
+                                } else {
+                                    clip.chapters.retain(|t| *t != chapter.title);
+                                }
+                            }
+                        }
+                    });
+            }
+        });
+    }
+
+    fn preview_ui(&mut self, ui: &mut egui::Ui) {
+        let preview = self.preview.clone();
+        match &mut *preview.lock().unwrap() {
+            Preview::Empty => {}
+            Preview::Loading(_) => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Fetching video info…");
+                });
+            }
+            Preview::Video(_, info) => {
+                ui.strong(&info.title);
+                let details: Vec<String> = [
+                    info.uploader.clone(),
+                    info.duration_pretty(),
+                    info.upload_date_pretty(),
+                    info.view_count.map(|v| format!("{} views", v)),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                ui.label(details.join(" · "));
+                if let Some(thumbnail) = &info.thumbnail {
+                    ui.hyperlink_to("🖼 Thumbnail", thumbnail);
+                }
+                if !info.formats.is_empty() {
+                    egui::CollapsingHeader::new(format!("Formats ({})", info.formats.len()))
+                        .id_source("formats")
+                        .show(ui, |ui| self.format_table.show(ui, &info.formats, &mut self.format));
+                }
+            }
+            Preview::Playlist(_, playlist, selected) => {
+                ui.strong(playlist.title.as_deref().unwrap_or("Playlist"));
+                let count = selected.iter().filter(|s| **s).count();
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} of {} videos selected", count, playlist.entries.len()));
+                    if ui.button("All").clicked() {
+                        selected.fill(true);
+                    }
+                    if ui.button("None").clicked() {
+                        selected.fill(false);
+                    }
+                });
+                egui::ScrollArea::vertical()
+                    .id_source("playlist")
+                    .max_height(240.0)
+                    .show(ui, |ui| {
+                        for (entry, ticked) in playlist.entries.iter().zip(selected.iter_mut()) {
+                            let mut text = format!("{}. {}", entry.index, entry.title.as_deref().unwrap_or(&entry.id));
+                            if let Some(d) = entry.duration_pretty() {
+                                text += &format!(" ({})", d);
+                            }
+                            ui.checkbox(ticked, text);
+                        }
+                    });
+            }
+            Preview::Failed(_, e) => {
+                ui.colored_label(egui::Color32::RED, format!("❌ {}", e));
+                if let Some(hint) = e.hint() {
+                    ui.label(format!("💡 {}", hint));
+                }
+            }
+        }
+    }
+
+    fn jobs_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("📥 Queue");
+            let mut max = self.queue.max_concurrency();
+            ui.label("Parallel:");
+            if ui.add(egui::DragValue::new(&mut max).clamp_range(1..=8)).changed() {
+                self.queue.set_max_concurrency(max);
+                self.settings.concurrency = max;
+            }
+            if ui.button("🧹 Clear finished").clicked() {
+                self.queue.clear_finished();
+            }
+            ui.checkbox(&mut self.settings.delete_partials, "Delete .part files on cancel");
+        });
+
+        for entry in self.queue.jobs() {
+            ui.horizontal(|ui| {
+                ui.label(format!("#{}", entry.id));
+                ui.label(entry.state.label());
+                let bar = match entry.state {
+                    JobState::Done => egui::ProgressBar::new(1.0),
+                    _ => egui::ProgressBar::new(entry.progress),
+                };
+                ui.add(bar.desired_width(160.0).show_percentage());
+                if entry.state == JobState::Running
+                    && let Some(transfer) = &entry.transfer
+                {
+                    ui.label(transfer.summary());
+                }
+                let active = matches!(entry.state, JobState::Queued | JobState::Running);
+                if active && ui.button("⏸ Pause").clicked() {
+                    self.queue.pause(entry.id);
+                }
+                if entry.state == JobState::Paused && ui.button("▶ Resume").clicked() {
+                    self.queue.resume(entry.id);
+                }
+                if !entry.state.is_finished() && ui.button("✖ Cancel").clicked() {
+                    let request = CancelRequest {
+                        delete_partials: self.settings.delete_partials,
+                    };
+                    self.queue.cancel(entry.id, request);
+                }
+                let mut text = format!("{} | {}", entry.job.display_name(), entry.job.format_label());
+                if let Some(backend) = entry.backend {
+                    text += &format!(" | via {}", backend);
+                }
+                ui.label(text);
+            });
+
+            if let Some(error) = &entry.error {
+                ui.colored_label(egui::Color32::RED, format!("❌ {}", error));
+                if let Some(hint) = error.hint() {
+                    ui.label(format!("💡 {}", hint));
+                }
+            }
+
+            if !entry.log.is_empty() {
+                egui::CollapsingHeader::new("Log")
+                    .id_source(("job-log", entry.id))
+                    .show(ui, |ui| {
+                        for line in &entry.log {
+                            ui.monospace(line);
+                        }
+                    });
+            }
+            if !entry.stderr.is_empty() {
+                egui::CollapsingHeader::new("stderr")
+                    .id_source(("job-stderr", entry.id))
+                    .show(ui, |ui| {
+                        for line in &entry.stderr {
+                            ui.monospace(line);
+                        }
+                    });
+            }
+        }
+    }
+}
+
+/// A checkbox that turns an optional setting on, with its value next to it,
+/// or `unset` while it is off. Ticking it starts from `default`.
+fn optional_value<T: egui::emath::Numeric>(
+    ui: &mut egui::Ui,
+    value: &mut Option<T>,
+    default: T,
+    suffix: &str,
+    unset: &str,
+) {
+    ui.horizontal(|ui| {
+        let mut on = value.is_some();
+        if ui.checkbox(&mut on, "").changed() {
+            *value = on.then_some(default);
+        }
+        match value {
+            Some(v) => {
+                ui.add(egui::DragValue::new(v).clamp_range(T::from_f64(0.0)..=T::MAX).suffix(suffix));
+            }
+            None => {
+                ui.weak(unset);
+            }
+        }
+    });
+}
+
+/// Stand-in metadata for the template preview before any URL is fetched.
+fn sample_info() -> VideoInfo {
+    VideoInfo {
+        id: "dQw4w9WgXcQ".into(),
+        title: "Sample Video".into(),
+        uploader: Some("Some Channel".into()),
+        upload_date: Some("20240131".into()),
+        ..VideoInfo::default()
+    }
+}
@@ -0,0 +1,58 @@
+// Opt-in clipboard monitor: a background thread polls the clipboard and
+// reports newly copied links to supported sites
+
+use rstube::sites;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(750);
+
+/// Stops polling when dropped.
+pub struct ClipboardWatcher {
+    urls: mpsc::Receiver<String>,
+    stop: Arc<AtomicBool>,
+}
+
+impl ClipboardWatcher {
+    /// Whatever is on the clipboard right now is ignored; only later copies count.
+    pub fn start() -> Result<Self, arboard::Error> {
+        let mut clipboard = arboard::Clipboard::new()?;
+        let (tx, urls) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let stopped = stop.clone();
+        thread::spawn(move || {
+            let mut last = clipboard.get_text().ok();
+            while !stopped.load(Ordering::Relaxed) {
+                thread::sleep(POLL_INTERVAL);
+                let Ok(text) = clipboard.get_text() else {
+                    continue;
+                };
+                if last.as_deref() == Some(text.as_str()) {
+                    continue;
+                }
+                if let Some(url) = sites::supported_url(&text)
+                    && tx.send(url).is_err()
+                {
+                    break;
+                }
+                last = Some(text);
+            }
+        });
+
+        Ok(Self { urls, stop })
+    }
+
+    /// Links copied since the last call.
+    pub fn drain(&self) -> Vec<String> {
+        self.urls.try_iter().collect()
+    }
+}
+
+impl Drop for ClipboardWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
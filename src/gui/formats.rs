@@ -0,0 +1,134 @@
+// Sortable table of the formats a video offers, with video/audio picking
+
+use eframe::egui;
+use rstube::units::human_bytes;
+use rstube::{Format, FormatInfo};
+use std::cmp::Ordering;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Id,
+    Resolution,
+    Fps,
+    Codec,
+    Bitrate,
+    Container,
+    Size,
+}
+
+const COLUMNS: [(Column, &str); 7] = [
+    (Column::Id, "ID"),
+    (Column::Resolution, "Resolution"),
+    (Column::Fps, "FPS"),
+    (Column::Codec, "Codec"),
+    (Column::Bitrate, "Bitrate"),
+    (Column::Container, "Container"),
+    (Column::Size, "Size"),
+];
+
+pub struct FormatTable {
+    sort: Column,
+    ascending: bool,
+}
+
+impl Default for FormatTable {
+    fn default() -> Self {
+        Self {
+            sort: Column::Resolution,
+            ascending: false,
+        }
+    }
+}
+
+impl FormatTable {
+    /// Draws the table. Picking a row turns `format` into `Format::Custom`.
+    pub fn show(&mut self, ui: &mut egui::Ui, formats: &[FormatInfo], format: &mut Format) {
+        let (mut video, mut audio) = match format {
+            Format::Custom { video, audio } => (video.clone(), audio.clone()),
+            _ => (None, None),
+        };
+
+        let mut rows: Vec<&FormatInfo> = formats.iter().collect();
+        rows.sort_by(|a, b| {
+            let ord = compare(self.sort, a, b);
+            if self.ascending { ord } else { ord.reverse() }
+        });
+
+        let mut changed = false;
+        egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+            egui::Grid::new("formats").striped(true).show(ui, |ui| {
+                for (column, title) in COLUMNS {
+                    let arrow = match (self.sort == column, self.ascending) {
+                        (true, true) => " ⏶",
+                        (true, false) => " ⏷",
+                        _ => "",
+                    };
+                    if ui.button(format!("{}{}", title, arrow)).clicked() {
+                        if self.sort == column {
+                            self.ascending = !self.ascending;
+                        } else {
+                            self.sort = column;
+                            self.ascending = true;
+                        }
+                    }
+                }
+                ui.label("Pick");
+                ui.end_row();
+
+                for f in rows {
+                    ui.monospace(&f.format_id);
+                    ui.label(f.resolution());
+                    ui.label(f.fps.map(|v| format!("{:.0}", v)).unwrap_or_default());
+                    ui.label(f.codec());
+                    ui.label(f.tbr.map(|v| format!("{:.0}k", v)).unwrap_or_default());
+                    ui.label(f.ext.as_deref().unwrap_or("?"));
+                    ui.label(f.size().map(human_bytes).unwrap_or_default());
+
+                    let id = Some(f.format_id.clone());
+                    if f.has_video() {
+                        let picked = video == id;
+                        if ui.selectable_label(picked, "🎞 Video").clicked() {
+                            video = if picked { None } else { id };
+                            changed = true;
+                        }
+                    } else if f.has_audio() {
+                        let picked = audio == id;
+                        if ui.selectable_label(picked, "🔊 Audio").clicked() {
+                            audio = if picked { None } else { id };
+                            changed = true;
+                        }
+                    } else {
+                        ui.label("");
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+
+        if changed {
+            *format = match (video, audio) {
+                (None, None) => Format::BestVideo,
+                (video, audio) => Format::Custom { video, audio },
+            };
+        }
+    }
+}
+
+fn compare(column: Column, a: &FormatInfo, b: &FormatInfo) -> Ordering {
+    match column {
+        Column::Id => a.format_id.cmp(&b.format_id),
+        Column::Resolution => (a.height, a.width).cmp(&(b.height, b.width)),
+        Column::Fps => compare_f64(a.fps, b.fps),
+        Column::Codec => a.codec().cmp(b.codec()),
+        Column::Bitrate => compare_f64(a.tbr, b.tbr),
+        Column::Container => a.ext.cmp(&b.ext),
+        Column::Size => a.size().cmp(&b.size()),
+    }
+}
+
+fn compare_f64(a: Option<f64>, b: Option<f64>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    }
+}
@@ -0,0 +1,22 @@
+// Widgets for the egui front end
+
+mod app;
+pub mod clipboard;
+pub mod formats;
+
+use app::DownloaderApp;
+use rstube::Settings;
+
+/// `load_error` is why the config file couldn't be read; while it is set the
+/// app leaves the file alone.
+pub fn run(settings: Settings, load_error: Option<String>) -> Result<(), eframe::Error> {
+    let mut options = eframe::NativeOptions::default();
+    if let Some(size) = settings.window_size {
+        options.viewport = options.viewport.with_inner_size(size);
+    }
+    eframe::run_native(
+        "Rust YouTube Downloader",
+        options,
+        Box::new(|_| Box::new(DownloaderApp::new(settings, load_error))),
+    )
+}
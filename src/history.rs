@@ -0,0 +1,214 @@
+// Record of finished downloads, kept as a JSON-lines file so every entry is
+// a single append and a crash can at worst truncate the last line.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::audio::AudioOptions;
+use crate::store;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HistoryItem {
+    pub url: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    pub format: String,
+    /// Codec and bitrate of an audio download.
+    #[serde(default)]
+    pub audio: Option<AudioOptions>,
+    pub status: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Final file yt-dlp wrote, when it reported one.
+    pub output: Option<PathBuf>,
+    /// Size of `output` once the job finished.
+    pub bytes: Option<u64>,
+    /// yt-dlp's exit code; `None` if it never started or was killed.
+    pub exit_code: Option<i32>,
+    /// Name of the cookie profile the job signed in with.
+    #[serde(default)]
+    pub cookies: Option<String>,
+    /// The site wanted a signed-in or different account.
+    #[serde(default)]
+    pub auth_failure: bool,
+    /// What did the transfer, e.g. `yt-dlp` or `HTTP`; `None` in older entries
+    /// and for jobs that never started.
+    #[serde(default)]
+    pub backend: Option<String>,
+}
+
+impl HistoryItem {
+    /// Title when known, otherwise the URL.
+    pub fn display_name(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.url)
+    }
+
+    pub fn duration(&self) -> chrono::Duration {
+        self.finished_at - self.started_at
+    }
+}
+
+/// Shared history list, optionally backed by a file. Cheap to clone.
+#[derive(Clone, Default)]
+pub struct History {
+    items: Arc<Mutex<Vec<HistoryItem>>>,
+    path: Option<PathBuf>,
+}
+
+impl History {
+    /// History that is never written to disk.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Default location of the history file.
+    pub fn default_path() -> Option<PathBuf> {
+        store::data_dir().map(|d| d.join("history.jsonl"))
+    }
+
+    /// Loads the file at `path` and appends every new entry to it.
+    /// Lines that don't parse, such as one cut short by a crash, are skipped.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let mut items = Vec::new();
+
+        match fs::File::open(&path) {
+            Ok(file) => {
+                // Bytes rather than strings, so a line cut off mid-character is
+                // skipped too instead of failing the whole load.
+                for line in BufReader::new(file).split(b'\n') {
+                    if let Ok(item) = serde_json::from_slice(&line?) {
+                        items.push(item);
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        Ok(Self {
+            items: Arc::new(Mutex::new(items)),
+            path: Some(path),
+        })
+    }
+
+    /// Oldest first.
+    pub fn items(&self) -> Vec<HistoryItem> {
+        self.items.lock().unwrap().clone()
+    }
+
+    pub fn push(&self, item: HistoryItem) {
+        if let Some(path) = &self.path
+            && let Err(e) = append(path, &item)
+        {
+            eprintln!("Failed to write history to {}: {}", path.display(), e);
+        }
+        self.items.lock().unwrap().push(item);
+    }
+}
+
+fn append(path: &Path, item: &HistoryItem) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut line = serde_json::to_vec(item).map_err(io::Error::other)?;
+    line.push(b'\n');
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    // A previous crash may have left a partial line; start on a fresh one.
+    if file.metadata()?.len() > 0 && !ends_with_newline(path)? {
+        line.insert(0, b'\n');
+    }
+    file.write_all(&line)?;
+    file.sync_data()
+}
+
+fn ends_with_newline(path: &Path) -> io::Result<bool> {
+    let mut file = fs::File::open(path)?;
+    file.seek(SeekFrom::End(-1))?;
+    let mut last = [0u8; 1];
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rstube-history-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("history.jsonl")
+    }
+
+    fn item(url: &str) -> HistoryItem {
+        let now = Utc::now();
+        HistoryItem {
+            url: url.into(),
+            title: Some(format!("Title of {}", url)),
+            format: "Best Video".into(),
+            audio: None,
+            status: "Completed".into(),
+            started_at: now,
+            finished_at: now,
+            output: Some(PathBuf::from("/tmp/video.mp4")),
+            bytes: Some(1024),
+            exit_code: Some(0),
+            cookies: None,
+            auth_failure: false,
+            backend: Some("yt-dlp".into()),
+        }
+    }
+
+    fn urls(history: &History) -> Vec<String> {
+        history.items().into_iter().map(|i| i.url).collect()
+    }
+
+    #[test]
+    fn round_trips_through_the_file() {
+        let path = temp_file("round-trip");
+        let history = History::open(&path).unwrap();
+        assert!(history.items().is_empty());
+        history.push(item("https://a"));
+        history.push(item("https://b"));
+
+        let reopened = History::open(&path).unwrap();
+        assert_eq!(urls(&reopened), ["https://a", "https://b"]);
+        let first = &reopened.items()[0];
+        assert_eq!(first.title.as_deref(), Some("Title of https://a"));
+        assert_eq!(first.bytes, Some(1024));
+        assert_eq!(first.backend.as_deref(), Some("yt-dlp"));
+    }
+
+    #[test]
+    fn truncated_last_line_is_skipped() {
+        let path = temp_file("truncated");
+        History::open(&path).unwrap().push(item("https://a"));
+        let whole = serde_json::to_string(&item("https://\u{e9}t\u{e9}")).unwrap();
+        // Cut inside the two-byte "é" of the title, as a crash mid-write could.
+        let cut = whole.rfind('\u{e9}').unwrap() + 1;
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&whole.as_bytes()[..cut]).unwrap();
+
+        assert_eq!(urls(&History::open(&path).unwrap()), ["https://a"]);
+    }
+
+    #[test]
+    fn push_after_a_truncated_line_starts_a_new_one() {
+        let path = temp_file("fresh-line");
+        History::open(&path).unwrap().push(item("https://a"));
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"url\":\"https://cut").unwrap();
+        assert!(!ends_with_newline(&path).unwrap());
+
+        History::open(&path).unwrap().push(item("https://b"));
+
+        assert!(ends_with_newline(&path).unwrap());
+        assert_eq!(urls(&History::open(&path).unwrap()), ["https://a", "https://b"]);
+    }
+}
@@ -0,0 +1,393 @@
+// Direct downloads of plain file links over HTTP, for URLs that already are a
+// media file rather than a page yt-dlp would have to read
+
+use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE, RANGE};
+use reqwest::StatusCode;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use std::{error::Error as _, io};
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use url::Url;
+
+use crate::backend::{Backend, BoxFuture};
+use crate::download::{DownloadEvent, DownloadHandle, DownloadJob};
+use crate::embed::EmbedOptions;
+use crate::error::{DownloadError, FailureKind};
+use crate::format::Format;
+use crate::info::{FormatInfo, VideoInfo};
+use crate::network::{IpVersion, NetworkOptions};
+use crate::playlist::Media;
+use crate::progress::{ProgressEvent, ProgressStatus};
+use crate::template;
+use crate::units::human_bytes;
+
+/// Extensions of links fetched directly instead of through yt-dlp.
+pub const EXTENSIONS: &[&str] = &[
+    "mp4", "m4v", "mkv", "webm", "mov", "avi", "mp3", "m4a", "aac", "ogg", "oga", "opus", "flac", "wav",
+];
+
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "m4a", "aac", "ogg", "oga", "opus", "flac", "wav"];
+
+/// yt-dlp's default, used when the network settings leave retries unset.
+const DEFAULT_RETRIES: u32 = 10;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Fetches the file as is, so it only takes jobs with no format, embedding,
+/// subtitle, SponsorBlock, section or cookie options. A paused download continues from its
+/// `.part` file when the server supports ranges.
+#[derive(Clone, Debug, Default)]
+pub struct HttpBackend {
+    /// Shared between clones, like the yt-dlp downloader's.
+    network: Arc<Mutex<NetworkOptions>>,
+}
+
+impl Backend for HttpBackend {
+    fn name(&self) -> &'static str {
+        "HTTP"
+    }
+
+    /// Only plain downloads of a file link: anything that needs yt-dlp's
+    /// post-processing or a signed-in session goes to yt-dlp instead.
+    fn handles(&self, job: &DownloadJob) -> bool {
+        let plain = job.format == Format::BestVideo
+            && job.embed == EmbedOptions::default()
+            && !job.sponsorblock.is_enabled()
+            && job.sections.is_empty()
+            && job.subtitles.is_none()
+            && job.cookies.is_none();
+        let Ok(url) = Url::parse(&job.url) else {
+            return false;
+        };
+        plain
+            && matches!(url.scheme(), "http" | "https")
+            && file_name(&url)
+                .and_then(|n| extension(&n))
+                .is_some_and(|ext| EXTENSIONS.contains(&ext.as_str()))
+    }
+
+    fn inspect<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Media, DownloadError>> {
+        Box::pin(async move { Ok(Media::Video(Box::new(self.info(url).await?))) })
+    }
+
+    fn start(&self, job: DownloadJob) -> DownloadHandle {
+        let (handle, tx) = DownloadHandle::channel();
+        let canceller = handle.canceller();
+        let backend = self.clone();
+
+        tokio::spawn(async move {
+            let dest = destination(&job);
+            let result = tokio::select! {
+                result = backend.download(&job, &dest, &tx) => result,
+                request = canceller.cancelled() => {
+                    if request.delete_partials {
+                        let _ = fs::remove_file(part_path(&dest)).await;
+                    }
+                    Err(DownloadError::Cancelled)
+                }
+            };
+            let _ = tx.send(DownloadEvent::Finished(result));
+        });
+        handle
+    }
+
+    fn set_network(&self, network: &NetworkOptions) {
+        *self.network.lock().unwrap() = network.clone();
+    }
+}
+
+/// Whether a failed attempt is worth repeating.
+enum Attempt {
+    Retry(DownloadError),
+    Fatal(DownloadError),
+}
+
+impl HttpBackend {
+    /// Name, size and type of the file from a HEAD request.
+    async fn info(&self, url: &str) -> Result<VideoInfo, DownloadError> {
+        let parsed = Url::parse(url).map_err(|e| failure(FailureKind::UnsupportedUrl, e.to_string()))?;
+        let name = file_name(&parsed).unwrap_or_else(|| "download".into());
+        let ext = extension(&name);
+        let network = self.network.lock().unwrap().clone();
+
+        let response = client(&network)?.head(url).send().await.map_err(request_error)?;
+        let status = response.status();
+        // Some servers refuse HEAD; the download itself will tell.
+        if !status.is_success() && !matches!(status, StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED) {
+            return Err(status_error(status));
+        }
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v: &reqwest::header::HeaderValue| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let size = header(CONTENT_LENGTH).and_then(|l| l.parse().ok());
+        let mime = header(CONTENT_TYPE);
+
+        let audio = ext.as_deref().is_some_and(|e| AUDIO_EXTENSIONS.contains(&e));
+        let format = FormatInfo {
+            format_id: "direct".into(),
+            format_note: mime,
+            ext: ext.clone(),
+            vcodec: audio.then(|| "none".to_string()),
+            filesize: size,
+            ..FormatInfo::default()
+        };
+        Ok(VideoInfo {
+            id: name.clone(),
+            title: stem(&name).to_string(),
+            webpage_url: Some(url.to_string()),
+            formats: vec![format],
+            ..VideoInfo::default()
+        })
+    }
+
+    async fn download(
+        &self,
+        job: &DownloadJob,
+        dest: &Path,
+        tx: &mpsc::UnboundedSender<DownloadEvent>,
+    ) -> Result<(), DownloadError> {
+        let network = self.network.lock().unwrap().clone();
+        let client = client(&network)?;
+        if let Some(dir) = dest.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).await.map_err(io_error)?;
+        }
+        if let Some(secs) = network.sleep_interval.filter(|s| *s > 0.0) {
+            tokio::time::sleep(Duration::from_secs_f64(secs)).await;
+        }
+
+        let _ = tx.send(DownloadEvent::Started);
+        let _ = tx.send(DownloadEvent::Log(format!("Downloading {} directly over HTTP", job.url)));
+        let _ = tx.send(DownloadEvent::Output(dest.to_path_buf()));
+
+        let part = part_path(dest);
+        let retries = network.retries.unwrap_or(DEFAULT_RETRIES);
+        let mut attempt = 0;
+        loop {
+            match transfer(&client, &job.url, dest, &part, &network, tx).await {
+                Ok(()) => break,
+                Err(Attempt::Retry(e)) if attempt < retries => {
+                    attempt += 1;
+                    let _ = tx.send(DownloadEvent::Stderr(format!("{}; retrying ({}/{})", e, attempt, retries)));
+                    tokio::time::sleep(Duration::from_secs(attempt.min(10) as u64)).await;
+                }
+                Err(Attempt::Retry(e) | Attempt::Fatal(e)) => return Err(e),
+            }
+        }
+
+        fs::rename(&part, dest).await.map_err(io_error)
+    }
+}
+
+/// One request: continues `part` where it ends, or starts it over if the
+/// server doesn't do ranges.
+async fn transfer(
+    client: &reqwest::Client,
+    url: &str,
+    dest: &Path,
+    part: &Path,
+    network: &NetworkOptions,
+    tx: &mpsc::UnboundedSender<DownloadEvent>,
+) -> Result<(), Attempt> {
+    let resume_from = fs::metadata(part).await.map(|m| m.len()).unwrap_or(0);
+    let mut request = client.get(url);
+    if resume_from > 0 {
+        request = request.header(RANGE, format!("bytes={}-", resume_from));
+    }
+    let mut response = request.send().await.map_err(|e| Attempt::Retry(request_error(e)))?;
+
+    let status = response.status();
+    let (mut file, mut downloaded) = match status {
+        StatusCode::PARTIAL_CONTENT => {
+            let file = OpenOptions::new().append(true).open(part).await;
+            (file.map_err(|e| Attempt::Fatal(io_error(e)))?, resume_from)
+        }
+        // The part file already holds everything.
+        StatusCode::RANGE_NOT_SATISFIABLE if resume_from > 0 => return Ok(()),
+        s if s.is_success() => (File::create(part).await.map_err(|e| Attempt::Fatal(io_error(e)))?, 0),
+        s if s.is_server_error() || s == StatusCode::TOO_MANY_REQUESTS => return Err(Attempt::Retry(status_error(s))),
+        s => return Err(Attempt::Fatal(status_error(s))),
+    };
+    let total = response.content_length().map(|n| n + downloaded);
+
+    let started = Instant::now();
+    let session_start = downloaded;
+    let mut reported = None::<Instant>;
+    let progress = |downloaded: u64, status: ProgressStatus| {
+        let elapsed = started.elapsed().as_secs_f64();
+        let speed = (elapsed > 0.0).then(|| (downloaded - session_start) as f64 / elapsed);
+        let eta = match (total, speed) {
+            (Some(total), Some(speed)) if speed > 0.0 => Some(total.saturating_sub(downloaded) as f64 / speed),
+            _ => None,
+        };
+        DownloadEvent::Progress(ProgressEvent {
+            status,
+            filename: Some(dest.to_path_buf()),
+            downloaded_bytes: Some(downloaded as f64),
+            total_bytes: total.map(|t| t as f64),
+            speed,
+            eta,
+            elapsed: Some(elapsed),
+            ..ProgressEvent::default()
+        })
+    };
+
+    while let Some(chunk) = response.chunk().await.map_err(|e| Attempt::Retry(request_error(e)))? {
+        file.write_all(&chunk).await.map_err(|e| Attempt::Fatal(io_error(e)))?;
+        downloaded += chunk.len() as u64;
+
+        if let Some(kib) = network.rate_limit.filter(|k| *k > 0) {
+            let due = (downloaded - session_start) as f64 / (kib as f64 * 1024.0);
+            let ahead = due - started.elapsed().as_secs_f64();
+            if ahead > 0.0 {
+                tokio::time::sleep(Duration::from_secs_f64(ahead)).await;
+            }
+        }
+        if reported.is_none_or(|r| r.elapsed() >= PROGRESS_INTERVAL) {
+            let _ = tx.send(progress(downloaded, ProgressStatus::Downloading));
+            reported = Some(Instant::now());
+        }
+    }
+    file.flush().await.map_err(|e| Attempt::Fatal(io_error(e)))?;
+
+    if let Some(total) = total
+        && downloaded < total
+    {
+        let message = format!("connection closed after {} of {}", human_bytes(downloaded), human_bytes(total));
+        return Err(Attempt::Retry(failure(FailureKind::Unknown, message)));
+    }
+    let _ = tx.send(progress(downloaded, ProgressStatus::Finished));
+    Ok(())
+}
+
+/// Client honouring the proxy, IP version and source address in `network`;
+/// also used for the SponsorBlock API.
+pub(crate) fn client(network: &NetworkOptions) -> Result<reqwest::Client, DownloadError> {
+    let mut builder = reqwest::Client::builder()
+        .user_agent(concat!("rstube/", env!("CARGO_PKG_VERSION")))
+        .connect_timeout(Duration::from_secs(30));
+
+    // HTTP(S) and SOCKS4/5 proxies, the same schemes yt-dlp takes.
+    let proxy = network.proxy.trim();
+    if !proxy.is_empty() {
+        builder = builder.proxy(reqwest::Proxy::all(proxy).map_err(request_error)?);
+    }
+
+    // Binding to the unspecified address of one family keeps connections to it.
+    let local = match (network.source_address.trim().parse::<IpAddr>(), network.ip_version) {
+        (Ok(addr), _) => Some(addr),
+        (Err(_), IpVersion::V4) => Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+        (Err(_), IpVersion::V6) => Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        (Err(_), IpVersion::Any) => None,
+    };
+    builder.local_address(local).build().map_err(request_error)
+}
+
+/// Where a job's file goes: the server's file name, in the folder the
+/// output template would put it in.
+fn destination(job: &DownloadJob) -> PathBuf {
+    let name = Url::parse(&job.url)
+        .ok()
+        .and_then(|u| file_name(&u))
+        .unwrap_or_else(|| "download".into());
+    let ext = extension(&name).unwrap_or_default();
+    let info = VideoInfo {
+        id: stem(&name).to_string(),
+        title: job.title.clone().unwrap_or_else(|| stem(&name).to_string()),
+        webpage_url: Some(job.url.clone()),
+        ..VideoInfo::default()
+    };
+    let folder = job
+        .output_template
+        .as_ref()
+        .and_then(|t| template::render(t, &info, job.playlist.as_ref(), &ext).ok())
+        .and_then(|p| Path::new(&p).parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+    job.output_dir.clone().unwrap_or_default().join(folder).join(name)
+}
+
+fn part_path(dest: &Path) -> PathBuf {
+    let mut part = dest.as_os_str().to_owned();
+    part.push(".part");
+    PathBuf::from(part)
+}
+
+/// Last segment of the URL's path, decoded and safe to use as a file name.
+fn file_name(url: &Url) -> Option<String> {
+    let segment = url.path_segments()?.next_back().filter(|s| !s.is_empty())?;
+    let name = percent_decode(segment).replace(['/', '\\'], "_");
+    (!name.trim_matches('.').is_empty()).then_some(name)
+}
+
+fn extension(name: &str) -> Option<String> {
+    let (_, ext) = name.rsplit_once('.')?;
+    (!ext.is_empty()).then(|| ext.to_lowercase())
+}
+
+fn stem(name: &str) -> &str {
+    name.rsplit_once('.').map_or(name, |(stem, _)| stem)
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn failure(kind: FailureKind, message: String) -> DownloadError {
+    DownloadError::Failed {
+        kind,
+        exit_code: None,
+        message: Some(message),
+    }
+}
+
+fn status_error(status: StatusCode) -> DownloadError {
+    let kind = match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => FailureKind::LoginRequired,
+        StatusCode::TOO_MANY_REQUESTS => FailureKind::RateLimited,
+        StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS => FailureKind::GeoBlocked,
+        _ => FailureKind::Unknown,
+    };
+    failure(kind, format!("HTTP {}", status))
+}
+
+/// reqwest's message plus its causes, which say what actually went wrong.
+fn request_error(e: reqwest::Error) -> DownloadError {
+    let mut message = e.to_string();
+    let mut source = e.source();
+    while let Some(cause) = source {
+        message += &format!(": {}", cause);
+        source = cause.source();
+    }
+    failure(FailureKind::Unknown, message)
+}
+
+fn io_error(e: io::Error) -> DownloadError {
+    let kind = match e.kind() {
+        io::ErrorKind::StorageFull => FailureKind::DiskFull,
+        _ => FailureKind::Unknown,
+    };
+    failure(kind, e.to_string())
+}
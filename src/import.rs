@@ -0,0 +1,239 @@
+// Batch import of URL lists: one URL per line, optionally followed by a
+// format override after whitespace or a comma (`url,audio`), or CSV with a
+// `url` header row. Blank lines and `#` comments are skipped, and repeated
+// URLs are only queued once.
+
+use std::collections::HashMap;
+use std::{fs, io, path::Path};
+
+use crate::format::Format;
+
+#[derive(Clone, Debug)]
+pub struct ImportEntry {
+    pub url: String,
+    /// Per-line override; `None` means use the default format.
+    pub format: Option<Format>,
+    /// 1-based line number in the file.
+    pub line: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct Rejected {
+    pub line: usize,
+    pub text: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ImportReport {
+    pub entries: Vec<ImportEntry>,
+    pub rejected: Vec<Rejected>,
+}
+
+impl ImportReport {
+    pub fn summary(&self) -> String {
+        match self.rejected.len() {
+            0 => format!("Imported {} URLs", self.entries.len()),
+            n => format!("Imported {} URLs, rejected {} lines", self.entries.len(), n),
+        }
+    }
+}
+
+pub fn read(path: &Path) -> io::Result<ImportReport> {
+    Ok(parse(&fs::read_to_string(path)?))
+}
+
+pub fn parse(text: &str) -> ImportReport {
+    let mut report = ImportReport::default();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut first = true;
+    let mut csv = false;
+
+    for (i, raw) in text.lines().enumerate() {
+        let line = i + 1;
+        let trimmed = raw.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        // A CSV header row, possibly below some comments.
+        let header = first && trimmed.split(',').next().is_some_and(|f| unquote(f).eq_ignore_ascii_case("url"));
+        first = false;
+        if header {
+            csv = true;
+            continue;
+        }
+
+        let reject = |reason: String| Rejected {
+            line,
+            text: trimmed.to_string(),
+            reason,
+        };
+
+        let (url, format) = match split_line(trimmed, csv) {
+            Ok(split) => split,
+            Err(reason) => {
+                report.rejected.push(reject(reason));
+                continue;
+            }
+        };
+        if let Err(reason) = validate(url) {
+            report.rejected.push(reject(reason));
+            continue;
+        }
+        if let Some(first) = seen.get(url) {
+            report.rejected.push(reject(format!("duplicate of line {}", first)));
+            continue;
+        }
+
+        seen.insert(url.to_string(), line);
+        report.entries.push(ImportEntry {
+            url: url.to_string(),
+            format,
+            line,
+        });
+    }
+    report
+}
+
+/// Splits a line into its URL and format. Quoted URLs and rows below a CSV
+/// header split at the comma like any CSV. Elsewhere URLs may contain commas
+/// themselves (`?ids=1,2`), so a comma only counts before a format name;
+/// format ids go after a space, as in `url 137+140`.
+fn split_line(line: &str, csv: bool) -> Result<(&str, Option<Format>), String> {
+    if let Some(rest) = line.strip_prefix('"') {
+        let (url, rest) = rest.split_once('"').ok_or("unclosed quote")?;
+        let format = match rest.trim_start() {
+            "" => "",
+            rest => rest.strip_prefix(',').ok_or("expected a comma after the quoted URL")?,
+        };
+        return Ok((url.trim(), parse_format(format)?));
+    }
+
+    let comma = if csv {
+        line.split_once(',')
+    } else {
+        line.rsplit_once(',').filter(|(_, f)| is_format_name(unquote(f)))
+    };
+    match comma.or_else(|| line.split_once(char::is_whitespace)) {
+        Some((url, format)) => Ok((url.trim(), parse_format(format)?)),
+        None => Ok((line, None)),
+    }
+}
+
+/// An empty column or a name such as `audio`, which no query value is likely to be.
+fn is_format_name(text: &str) -> bool {
+    text.is_empty() || ["video", "audio", "best", "mp3"].iter().any(|n| text.eq_ignore_ascii_case(n))
+}
+
+fn parse_format(text: &str) -> Result<Option<Format>, String> {
+    match unquote(text) {
+        "" => Ok(None),
+        text => text.parse().map(Some),
+    }
+}
+
+fn unquote(s: &str) -> &str {
+    s.trim().trim_matches('"').trim()
+}
+
+fn validate(url: &str) -> Result<(), String> {
+    // The URL parser would quietly encode them; here they mean a bad format column.
+    if url.contains(char::is_whitespace) {
+        return Err("not a URL: contains spaces".into());
+    }
+    let parsed = url::Url::parse(url).map_err(|e| format!("not a URL: {}", e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("unsupported scheme '{}'", parsed.scheme()));
+    }
+    if parsed.host_str().is_none_or(|h| h.is_empty()) {
+        return Err("missing host".into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(report: &ImportReport) -> Vec<&str> {
+        report.entries.iter().map(|e| e.url.as_str()).collect()
+    }
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let report = parse("# my list\n\nhttps://youtu.be/a\n  # indented comment\nhttps://youtu.be/b audio\n");
+        assert_eq!(urls(&report), ["https://youtu.be/a", "https://youtu.be/b"]);
+        assert_eq!(report.entries[0].line, 3);
+        assert_eq!(report.entries[1].format, Some(Format::AudioOnly));
+        assert!(report.rejected.is_empty());
+    }
+
+    #[test]
+    fn header_below_comments() {
+        let report = parse("# exported list\nurl,format\nhttps://youtu.be/a,audio\nhttps://youtu.be/b,\n");
+        assert_eq!(urls(&report), ["https://youtu.be/a", "https://youtu.be/b"]);
+        assert_eq!(report.entries[0].format, Some(Format::AudioOnly));
+        assert_eq!(report.entries[1].format, None);
+        assert!(report.rejected.is_empty());
+    }
+
+    #[test]
+    fn drops_duplicates() {
+        let report = parse("https://youtu.be/a\nhttps://youtu.be/a,audio\n");
+        assert_eq!(urls(&report), ["https://youtu.be/a"]);
+        assert_eq!(report.rejected.len(), 1);
+        assert_eq!(report.rejected[0].line, 2);
+        assert_eq!(report.rejected[0].reason, "duplicate of line 1");
+    }
+
+    #[test]
+    fn rejects_other_schemes_and_non_urls() {
+        let report = parse("ftp://example.com/a.mp4\nfile:///tmp/a.mp4\nnot a url\nhttps://youtu.be/a two words\n");
+        assert!(report.entries.is_empty());
+        assert_eq!(report.rejected.len(), 4);
+        assert_eq!(report.rejected[0].reason, "unsupported scheme 'ftp'");
+        assert_eq!(report.summary(), "Imported 0 URLs, rejected 4 lines");
+    }
+
+    #[test]
+    fn quoted_csv() {
+        let report = parse("\"https://example.com/feed?ids=1,2\",\"137+140\"\n\"https://youtu.be/a\"\n");
+        assert_eq!(urls(&report), ["https://example.com/feed?ids=1,2", "https://youtu.be/a"]);
+        assert_eq!(
+            report.entries[0].format,
+            Some(Format::Custom {
+                video: Some("137".into()),
+                audio: Some("140".into())
+            })
+        );
+        assert!(parse("\"https://youtu.be/a\n").rejected[0].reason.contains("quote"));
+    }
+
+    #[test]
+    fn urls_with_commas() {
+        let text = "https://example.com/feed?ids=1,2\n\
+            https://example.com/a.mp4?x=1,720p\n\
+            https://youtu.be/b 22\n\
+            https://youtu.be/c,audio\n";
+        let report = parse(text);
+        assert_eq!(
+            urls(&report),
+            [
+                "https://example.com/feed?ids=1,2",
+                "https://example.com/a.mp4?x=1,720p",
+                "https://youtu.be/b",
+                "https://youtu.be/c"
+            ]
+        );
+        assert_eq!(report.entries[0].format, None);
+        assert_eq!(report.entries[1].format, None);
+        assert_eq!(
+            report.entries[2].format,
+            Some(Format::Custom {
+                video: Some("22".into()),
+                audio: None
+            })
+        );
+        assert_eq!(report.entries[3].format, Some(Format::AudioOnly));
+    }
+}
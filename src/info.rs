@@ -0,0 +1,219 @@
+// Video metadata from `yt-dlp --dump-json`, fetched before anything is queued
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::units::human_duration;
+
+/// The parts of yt-dlp's info JSON the app uses. Unknown fields are ignored.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct VideoInfo {
+    pub id: String,
+    pub title: String,
+    pub uploader: Option<String>,
+    /// Seconds.
+    pub duration: Option<f64>,
+    /// `YYYYMMDD`, as yt-dlp reports it.
+    pub upload_date: Option<String>,
+    pub view_count: Option<u64>,
+    pub thumbnail: Option<String>,
+    pub webpage_url: Option<String>,
+    #[serde(default)]
+    pub formats: Vec<FormatInfo>,
+    /// Uploaded subtitles by language code.
+    #[serde(default)]
+    pub subtitles: BTreeMap<String, Vec<SubtitleTrack>>,
+    /// YouTube's auto-generated and auto-translated captions by language code.
+    #[serde(default)]
+    pub automatic_captions: BTreeMap<String, Vec<SubtitleTrack>>,
+    /// yt-dlp reports `null` for videos without chapters.
+    pub chapters: Option<Vec<Chapter>>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Chapter {
+    /// Seconds.
+    pub start_time: f64,
+    pub end_time: f64,
+    pub title: String,
+}
+
+/// One file format a subtitle language is offered in.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SubtitleTrack {
+    pub ext: Option<String>,
+    /// Language name, e.g. `English`.
+    pub name: Option<String>,
+}
+
+/// A subtitle language the video offers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubtitleLanguage {
+    pub code: String,
+    pub name: Option<String>,
+    /// Only available as auto-generated captions.
+    pub auto: bool,
+}
+
+/// One entry of the info JSON's `formats` list.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FormatInfo {
+    pub format_id: String,
+    pub format_note: Option<String>,
+    /// Container, e.g. `mp4` or `webm`.
+    pub ext: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fps: Option<f64>,
+    /// `"none"` for audio-only formats.
+    pub vcodec: Option<String>,
+    /// `"none"` for video-only formats.
+    pub acodec: Option<String>,
+    /// Total bitrate in kbit/s.
+    pub tbr: Option<f64>,
+    pub filesize: Option<u64>,
+    pub filesize_approx: Option<u64>,
+}
+
+impl FormatInfo {
+    pub fn has_video(&self) -> bool {
+        self.vcodec.as_deref().is_some_and(|c| c != "none")
+    }
+
+    pub fn has_audio(&self) -> bool {
+        self.acodec.as_deref().is_some_and(|c| c != "none")
+    }
+
+    /// `1920x1080`, `audio only`, or `?` when yt-dlp didn't say.
+    pub fn resolution(&self) -> String {
+        match (self.width, self.height) {
+            (Some(w), Some(h)) => format!("{}x{}", w, h),
+            (None, Some(h)) => format!("{}p", h),
+            _ if !self.has_video() && self.has_audio() => "audio only".into(),
+            _ => "?".into(),
+        }
+    }
+
+    /// Video codec for video formats, audio codec for audio-only ones.
+    pub fn codec(&self) -> &str {
+        let codec = if self.has_video() { &self.vcodec } else { &self.acodec };
+        codec.as_deref().unwrap_or("?")
+    }
+
+    /// Exact size if known, otherwise yt-dlp's estimate.
+    pub fn size(&self) -> Option<u64> {
+        self.filesize.or(self.filesize_approx)
+    }
+}
+
+impl VideoInfo {
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// `upload_date` as `YYYY-MM-DD`.
+    pub fn upload_date_pretty(&self) -> Option<String> {
+        let d = self.upload_date.as_deref()?;
+        if d.len() != 8 || !d.is_ascii() {
+            return Some(d.to_string());
+        }
+        Some(format!("{}-{}-{}", &d[..4], &d[4..6], &d[6..]))
+    }
+
+    pub fn duration_pretty(&self) -> Option<String> {
+        self.duration.map(|d| human_duration(d.max(0.0) as u64))
+    }
+
+    /// Uploaded subtitle languages first, then auto-generated ones not
+    /// already covered. Live chat replays are left out.
+    pub fn subtitle_languages(&self) -> Vec<SubtitleLanguage> {
+        let language = |(code, tracks): (&String, &Vec<SubtitleTrack>), auto| SubtitleLanguage {
+            code: code.clone(),
+            name: tracks.iter().find_map(|t| t.name.clone()),
+            auto,
+        };
+
+        let mut languages: Vec<SubtitleLanguage> = self
+            .subtitles
+            .iter()
+            .filter(|(code, _)| *code != "live_chat")
+            .map(|entry| language(entry, false))
+            .collect();
+        for entry in &self.automatic_captions {
+            if !self.subtitles.contains_key(entry.0) {
+                languages.push(language(entry, true));
+            }
+        }
+        languages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VIDEO: &str = r#"{
+        "id": "dQw4w9WgXcQ",
+        "title": "Never Gonna Give You Up",
+        "uploader": "Rick Astley",
+        "duration": 212.0,
+        "upload_date": "20091025",
+        "view_count": 1500000000,
+        "age_limit": 0,
+        "chapters": null,
+        "formats": [
+            {"format_id": "140", "ext": "m4a", "vcodec": "none", "acodec": "mp4a.40.2", "filesize": 3433514},
+            {"format_id": "137", "ext": "mp4", "width": 1920, "height": 1080, "fps": 25,
+             "vcodec": "avc1.640028", "acodec": "none", "filesize_approx": 80000000}
+        ],
+        "subtitles": {
+            "live_chat": [{"ext": "json"}],
+            "en": [{"ext": "vtt", "name": "English"}, {"ext": "srv3", "name": "English"}]
+        },
+        "automatic_captions": {
+            "en": [{"ext": "vtt", "name": "English"}],
+            "de": [{"ext": "vtt", "name": "German"}],
+            "ab": [{"ext": "vtt"}]
+        }
+    }"#;
+
+    #[test]
+    fn parses_a_video() {
+        let info = VideoInfo::from_json(VIDEO).unwrap();
+        assert_eq!(info.id, "dQw4w9WgXcQ");
+        assert_eq!(info.uploader.as_deref(), Some("Rick Astley"));
+        assert_eq!(info.upload_date_pretty().as_deref(), Some("2009-10-25"));
+        assert_eq!(info.duration_pretty(), Some(human_duration(212)));
+        assert!(info.chapters.is_none());
+
+        let [audio, video] = &info.formats[..] else {
+            panic!("{:?}", info.formats);
+        };
+        assert_eq!(audio.resolution(), "audio only");
+        assert_eq!((audio.codec(), audio.size()), ("mp4a.40.2", Some(3433514)));
+        assert_eq!(video.resolution(), "1920x1080");
+        assert_eq!((video.codec(), video.size()), ("avc1.640028", Some(80000000)));
+    }
+
+    #[test]
+    fn missing_optional_fields() {
+        let info = VideoInfo::from_json(r#"{"id": "x", "title": "t", "upload_date": "2024"}"#).unwrap();
+        assert!(info.formats.is_empty() && info.subtitles.is_empty());
+        assert_eq!(info.upload_date_pretty().as_deref(), Some("2024"));
+        assert!(VideoInfo::from_json(r#"{"title": "no id"}"#).is_err());
+    }
+
+    #[test]
+    fn uploaded_subtitles_come_before_auto_captions() {
+        let languages = VideoInfo::from_json(VIDEO).unwrap().subtitle_languages();
+        let language = |code: &str, name: Option<&str>, auto| SubtitleLanguage {
+            code: code.into(),
+            name: name.map(String::from),
+            auto,
+        };
+        assert_eq!(
+            languages,
+            [language("en", Some("English"), false), language("ab", None, true), language("de", Some("German"), true)]
+        );
+    }
+}
@@ -0,0 +1,52 @@
+// Rstube download engine
+// Drives yt-dlp and reports typed progress events, so the GUI and other tools
+// share one implementation.
+
+pub mod audio;
+pub mod backend;
+pub mod cookies;
+pub mod download;
+pub mod embed;
+pub mod error;
+pub mod format;
+pub mod history;
+pub mod http;
+pub mod import;
+pub mod info;
+pub mod network;
+pub mod playlist;
+mod process;
+pub mod progress;
+pub mod queue;
+pub mod sections;
+pub mod settings;
+pub mod sites;
+pub mod sponsorblock;
+pub mod store;
+pub mod subtitles;
+pub mod template;
+pub mod tools;
+pub mod units;
+pub mod video;
+
+pub use audio::{AudioCodec, AudioOptions};
+pub use backend::{Backend, Backends};
+pub use cookies::CookieProfile;
+pub use download::{CancelRequest, Canceller, DownloadEvent, DownloadHandle, DownloadJob, Downloader};
+pub use embed::EmbedOptions;
+pub use error::{DownloadError, FailureKind};
+pub use format::Format;
+pub use history::{History, HistoryItem};
+pub use http::HttpBackend;
+pub use import::ImportReport;
+pub use info::{Chapter, FormatInfo, SubtitleLanguage, VideoInfo};
+pub use network::{IpVersion, NetworkOptions};
+pub use playlist::{Media, Playlist, PlaylistEntry};
+pub use progress::{ProgressEvent, ProgressStatus};
+pub use queue::{JobEntry, JobId, JobState, Queue};
+pub use sections::Section;
+pub use settings::Settings;
+pub use sponsorblock::SponsorBlockOptions;
+pub use subtitles::SubtitleOptions;
+pub use tools::{ToolPaths, Toolchain};
+pub use video::{CodecOrder, Container, VideoOptions};
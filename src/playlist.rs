@@ -0,0 +1,161 @@
+// Playlist and channel expansion via `yt-dlp --flat-playlist`, so every video
+// can be queued as its own job
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::info::VideoInfo;
+use crate::units::human_duration;
+
+#[derive(Clone, Debug, Default)]
+pub struct Playlist {
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub uploader: Option<String>,
+    pub entries: Vec<PlaylistEntry>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct PlaylistEntry {
+    pub id: String,
+    pub url: Option<String>,
+    pub title: Option<String>,
+    /// Seconds.
+    pub duration: Option<f64>,
+    /// 1-based position across the whole expansion.
+    #[serde(skip)]
+    pub index: usize,
+}
+
+impl PlaylistEntry {
+    /// URL to hand to yt-dlp for this single video.
+    pub fn video_url(&self) -> String {
+        match &self.url {
+            Some(url) if url.starts_with("http") => url.clone(),
+            _ => format!("https://www.youtube.com/watch?v={}", self.id),
+        }
+    }
+
+    pub fn duration_pretty(&self) -> Option<String> {
+        self.duration.map(|d| human_duration(d.max(0.0) as u64))
+    }
+}
+
+/// What a URL points at.
+#[derive(Clone, Debug)]
+pub enum Media {
+    Video(Box<VideoInfo>),
+    Playlist(Playlist),
+}
+
+impl Media {
+    /// Parses the output of `--flat-playlist --dump-single-json`.
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        let value: Value = serde_json::from_str(json)?;
+        if value.get("_type").and_then(Value::as_str) != Some("playlist") {
+            return serde_json::from_value(value).map(|v| Media::Video(Box::new(v)));
+        }
+
+        let mut playlist = Playlist {
+            id: string_field(&value, "id"),
+            title: string_field(&value, "title"),
+            uploader: string_field(&value, "uploader"),
+            entries: Vec::new(),
+        };
+        collect_entries(&value, &mut playlist.entries);
+        for (i, entry) in playlist.entries.iter_mut().enumerate() {
+            entry.index = i + 1;
+        }
+        Ok(Media::Playlist(playlist))
+    }
+}
+
+/// Flattens nested playlists, such as a channel's tabs, into one list.
+fn collect_entries(value: &Value, out: &mut Vec<PlaylistEntry>) {
+    let Some(entries) = value.get("entries").and_then(Value::as_array) else {
+        return;
+    };
+    for entry in entries {
+        if entry.get("entries").is_some() {
+            collect_entries(entry, out);
+        } else if let Ok(e) = PlaylistEntry::deserialize(entry) {
+            out.push(e);
+        }
+    }
+}
+
+fn string_field(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playlist(json: &str) -> Playlist {
+        match Media::from_json(json).unwrap() {
+            Media::Playlist(p) => p,
+            Media::Video(v) => panic!("parsed as a video: {}", v.id),
+        }
+    }
+
+    #[test]
+    fn single_video() {
+        let media = Media::from_json(r#"{"id": "abc", "title": "A video", "_type": "video"}"#).unwrap();
+        assert!(matches!(media, Media::Video(v) if v.id == "abc"));
+        assert!(matches!(Media::from_json(r#"{"id": "abc", "title": "No type"}"#), Ok(Media::Video(_))));
+    }
+
+    #[test]
+    fn flat_playlist() {
+        let p = playlist(
+            r#"{
+                "_type": "playlist", "id": "PL1", "title": "Mix", "uploader": "Someone",
+                "entries": [
+                    {"_type": "url", "id": "a", "url": "https://youtu.be/a", "title": "A", "duration": 61.0},
+                    {"_type": "url", "id": "b", "url": "b", "title": null, "duration": null},
+                    {"_type": "url", "title": "no id, skipped"}
+                ]
+            }"#,
+        );
+        assert_eq!(p.id.as_deref(), Some("PL1"));
+        assert_eq!((p.title.as_deref(), p.uploader.as_deref()), (Some("Mix"), Some("Someone")));
+        let [a, b] = &p.entries[..] else {
+            panic!("{:?}", p.entries);
+        };
+        assert_eq!((a.index, a.video_url()), (1, "https://youtu.be/a".into()));
+        assert_eq!(a.duration_pretty(), Some(human_duration(61)));
+        // Not a link, so it's taken as a YouTube video id.
+        assert_eq!((b.index, b.video_url()), (2, "https://www.youtube.com/watch?v=b".into()));
+        assert_eq!(b.duration_pretty(), None);
+    }
+
+    #[test]
+    fn channel_tabs_are_flattened() {
+        let p = playlist(
+            r#"{
+                "_type": "playlist", "id": "UC1", "title": "A channel",
+                "entries": [
+                    {"_type": "playlist", "id": "UC1-videos", "title": "Videos", "entries": [
+                        {"id": "v1", "title": "First"},
+                        {"id": "v2", "title": "Second"}
+                    ]},
+                    {"_type": "playlist", "id": "UC1-shorts", "title": "Shorts", "entries": []},
+                    {"_type": "playlist", "id": "UC1-streams", "title": "Live", "entries": [
+                        {"id": "s1", "title": "Stream"}
+                    ]},
+                    {"_type": "url", "id": "loose", "title": "Loose"}
+                ]
+            }"#,
+        );
+        let entries: Vec<(usize, &str)> = p.entries.iter().map(|e| (e.index, e.id.as_str())).collect();
+        assert_eq!(entries, [(1, "v1"), (2, "v2"), (3, "s1"), (4, "loose")]);
+        assert_eq!(p.title.as_deref(), Some("A channel"));
+    }
+
+    #[test]
+    fn playlist_without_entries() {
+        let p = playlist(r#"{"_type": "playlist", "id": "empty"}"#);
+        assert!(p.entries.is_empty());
+    }
+}
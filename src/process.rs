@@ -0,0 +1,68 @@
+// Stopping yt-dlp together with the ffmpeg children it starts, and cleaning up
+// what an interrupted download leaves behind
+
+use std::{fs, path::Path, path::PathBuf, time::Duration};
+use tokio::process::{Child, Command};
+
+/// Puts the child in its own process group so the whole tree can be signalled.
+pub(crate) fn isolate(cmd: &mut Command) {
+    #[cfg(unix)]
+    cmd.process_group(0);
+    #[cfg(not(unix))]
+    let _ = cmd;
+}
+
+/// Asks the process tree to exit, and kills it if it hasn't after a grace period.
+pub(crate) async fn terminate(child: &mut Child) {
+    let Some(pid) = child.id() else {
+        return;
+    };
+
+    #[cfg(unix)]
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), libc::SIGTERM);
+    }
+    #[cfg(windows)]
+    {
+        let _ = std::process::Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .status();
+    }
+
+    if tokio::time::timeout(Duration::from_secs(5), child.wait()).await.is_err() {
+        #[cfg(unix)]
+        unsafe {
+            libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+        }
+        let _ = child.kill().await;
+    }
+}
+
+/// Removes `.part`, `.ytdl` and fragment files next to each destination.
+pub(crate) fn remove_partials(destinations: &[PathBuf]) {
+    for dest in destinations {
+        for suffix in [".part", ".ytdl"] {
+            let _ = fs::remove_file(with_suffix(dest, suffix));
+        }
+
+        let (Some(dir), Some(name)) = (dest.parent(), dest.file_name()) else {
+            continue;
+        };
+        let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+        let fragment_prefix = format!("{}.part-Frag", name.to_string_lossy());
+
+        if let Ok(entries) = fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                if entry.file_name().to_string_lossy().starts_with(&fragment_prefix) {
+                    let _ = fs::remove_file(entry.path());
+                }
+            }
+        }
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut s = path.as_os_str().to_owned();
+    s.push(suffix);
+    PathBuf::from(s)
+}
@@ -0,0 +1,161 @@
+// Machine-readable progress: yt-dlp prints each progress update as one JSON
+// object behind a fixed prefix, so nothing else on stdout can be mistaken for it.
+
+use serde::Deserialize;
+use std::path::PathBuf;
+
+use crate::units::{human_bytes, human_duration};
+
+/// Marks our progress lines in yt-dlp's output.
+pub(crate) const PREFIX: &str = "[rstube-progress] ";
+
+/// Value for yt-dlp's `--progress-template`.
+pub(crate) fn template() -> String {
+    format!("download:{}%(progress)j", PREFIX)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProgressStatus {
+    #[default]
+    Downloading,
+    Finished,
+    Error,
+    #[serde(other)]
+    Unknown,
+}
+
+/// One progress update for the file currently being downloaded.
+/// yt-dlp leaves out whatever it doesn't know yet, e.g. the total size of a live stream.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct ProgressEvent {
+    #[serde(default)]
+    pub status: ProgressStatus,
+    pub filename: Option<PathBuf>,
+    pub downloaded_bytes: Option<f64>,
+    pub total_bytes: Option<f64>,
+    pub total_bytes_estimate: Option<f64>,
+    /// Bytes per second.
+    pub speed: Option<f64>,
+    /// Seconds left.
+    pub eta: Option<f64>,
+    pub elapsed: Option<f64>,
+    pub fragment_index: Option<u64>,
+    pub fragment_count: Option<u64>,
+}
+
+impl ProgressEvent {
+    pub fn parse(line: &str) -> Option<Self> {
+        serde_json::from_str(line.strip_prefix(PREFIX)?).ok()
+    }
+
+    pub fn downloaded(&self) -> Option<u64> {
+        self.downloaded_bytes.map(|b| b as u64)
+    }
+
+    /// Exact size if known, otherwise yt-dlp's estimate.
+    pub fn total(&self) -> Option<u64> {
+        self.total_bytes.or(self.total_bytes_estimate).map(|b| b as u64)
+    }
+
+    /// Fraction of the file downloaded, from 0.0 to 1.0.
+    pub fn fraction(&self) -> Option<f32> {
+        if self.status == ProgressStatus::Finished {
+            return Some(1.0);
+        }
+        match (self.downloaded_bytes, self.total_bytes.or(self.total_bytes_estimate)) {
+            (Some(done), Some(total)) if total > 0.0 => Some((done / total).clamp(0.0, 1.0) as f32),
+            _ => match (self.fragment_index, self.fragment_count) {
+                (Some(i), Some(n)) if n > 0 => Some((i as f32 / n as f32).clamp(0.0, 1.0)),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn eta_secs(&self) -> Option<u64> {
+        self.eta.map(|e| e.max(0.0) as u64)
+    }
+
+    /// e.g. `"12.0 MiB / 48.3 MiB · 2.1 MiB/s · ETA 17s"`, leaving out unknown parts.
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        match (self.downloaded(), self.total()) {
+            (Some(done), Some(total)) => parts.push(format!("{} / {}", human_bytes(done), human_bytes(total))),
+            (Some(done), None) => parts.push(human_bytes(done)),
+            _ => {}
+        }
+        if let Some(speed) = self.speed {
+            parts.push(format!("{}/s", human_bytes(speed as u64)));
+        }
+        if let Some(eta) = self.eta_secs() {
+            parts.push(format!("ETA {}", human_duration(eta)));
+        }
+        parts.join(" · ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_progress_lines() {
+        let json = r#"{"status": "downloading", "filename": "a.mp4", "downloaded_bytes": 1024,
+            "total_bytes": 4096, "speed": 512.5, "eta": 6, "extra": null}"#;
+        let line = format!("{}{}", PREFIX, json);
+        let event = ProgressEvent::parse(&line).unwrap();
+        assert_eq!(event.status, ProgressStatus::Downloading);
+        assert_eq!(event.filename, Some(PathBuf::from("a.mp4")));
+        assert_eq!(event.downloaded(), Some(1024));
+        assert_eq!(event.total(), Some(4096));
+        assert_eq!(event.eta_secs(), Some(6));
+        assert_eq!(event.fraction(), Some(0.25));
+    }
+
+    #[test]
+    fn ignores_other_lines() {
+        assert_eq!(ProgressEvent::parse("[download] Destination: a.mp4"), None);
+        assert_eq!(ProgressEvent::parse(r#"{"status": "downloading"}"#), None);
+        assert_eq!(ProgressEvent::parse(&format!("{}not json", PREFIX)), None);
+    }
+
+    #[test]
+    fn unknown_statuses_still_parse() {
+        let event = ProgressEvent::parse(&format!("{}{}", PREFIX, r#"{"status": "postprocessing"}"#)).unwrap();
+        assert_eq!(event.status, ProgressStatus::Unknown);
+        assert_eq!(event.fraction(), None);
+    }
+
+    #[test]
+    fn fraction_falls_back_to_estimates_and_fragments() {
+        let estimate = ProgressEvent {
+            downloaded_bytes: Some(50.0),
+            total_bytes_estimate: Some(200.0),
+            ..ProgressEvent::default()
+        };
+        assert_eq!(estimate.fraction(), Some(0.25));
+
+        let fragments = ProgressEvent {
+            downloaded_bytes: Some(50.0),
+            fragment_index: Some(3),
+            fragment_count: Some(4),
+            ..ProgressEvent::default()
+        };
+        assert_eq!(fragments.fraction(), Some(0.75));
+
+        // Estimates can undershoot; never report more than all of it.
+        let over = ProgressEvent {
+            downloaded_bytes: Some(300.0),
+            total_bytes_estimate: Some(200.0),
+            ..ProgressEvent::default()
+        };
+        assert_eq!(over.fraction(), Some(1.0));
+
+        let finished = ProgressEvent {
+            status: ProgressStatus::Finished,
+            ..ProgressEvent::default()
+        };
+        assert_eq!(finished.fraction(), Some(1.0));
+        assert_eq!(ProgressEvent::default().fraction(), None);
+    }
+}
@@ -0,0 +1,589 @@
+// Download queue: every job gets its own id, state, progress and log, and at
+// most `max_concurrency` jobs run at once. Unfinished jobs can be saved to disk
+// and picked up again on the next launch.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::{fs, io};
+use tokio::runtime::Handle;
+
+use crate::backend::Backends;
+use crate::download::{CancelRequest, Canceller, DownloadEvent, DownloadHandle, DownloadJob};
+use crate::error::{DownloadError, FailureKind};
+use crate::format::Format;
+use crate::history::{History, HistoryItem};
+use crate::progress::ProgressEvent;
+use crate::store;
+
+pub type JobId = u64;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JobState {
+    Queued,
+    Running,
+    Paused,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn label(&self) -> &'static str {
+        match self {
+            JobState::Queued => "Queued",
+            JobState::Running => "Running",
+            JobState::Paused => "Paused",
+            JobState::Done => "Done",
+            JobState::Failed => "Failed",
+            JobState::Cancelled => "Cancelled",
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobState::Done | JobState::Failed | JobState::Cancelled)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct JobEntry {
+    pub id: JobId,
+    pub job: DownloadJob,
+    pub state: JobState,
+    pub progress: f32,
+    /// Most recent progress report, for speed, ETA and size.
+    pub transfer: Option<ProgressEvent>,
+    pub log: Vec<String>,
+    /// What yt-dlp wrote to stderr.
+    pub stderr: Vec<String>,
+    /// Why the job failed, once it has.
+    pub error: Option<DownloadError>,
+    pub started_at: Option<DateTime<Utc>>,
+    /// Latest output file yt-dlp reported.
+    pub output: Option<PathBuf>,
+    /// Name of the backend doing the transfer, once started.
+    pub backend: Option<&'static str>,
+}
+
+struct Inner {
+    jobs: Vec<JobEntry>,
+    next_id: JobId,
+    max_concurrency: usize,
+    cancellers: HashMap<JobId, Canceller>,
+    // Running jobs whose stop was a pause rather than a cancel.
+    pausing: HashSet<JobId>,
+    store_path: Option<PathBuf>,
+}
+
+/// A job as written to the queue file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedJob {
+    pub job: DownloadJob,
+    pub paused: bool,
+}
+
+/// Reads a queue file without starting anything. A missing file is an empty queue.
+pub fn read_saved(path: &Path) -> io::Result<Vec<SavedJob>> {
+    match fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(io::Error::other),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Cheap to clone; all clones share the same jobs.
+#[derive(Clone)]
+pub struct Queue {
+    inner: Arc<Mutex<Inner>>,
+    history: History,
+    backends: Backends,
+    rt: Handle,
+}
+
+impl Queue {
+    pub fn new(backends: Backends, history: History, rt: Handle, max_concurrency: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                jobs: Vec::new(),
+                next_id: 1,
+                max_concurrency: max_concurrency.max(1),
+                cancellers: HashMap::new(),
+                pausing: HashSet::new(),
+                store_path: None,
+            })),
+            history,
+            backends,
+            rt,
+        }
+    }
+
+    /// Default location of the queue file.
+    pub fn default_store_path() -> Option<PathBuf> {
+        store::data_dir().map(|d| d.join("queue.json"))
+    }
+
+    /// Loads jobs saved at `path` and keeps the file up to date from now on.
+    /// Jobs that were running when the app closed come back as queued.
+    pub fn persist_to(&self, path: impl Into<PathBuf>) -> io::Result<()> {
+        let path = path.into();
+        let saved = read_saved(&path)?;
+
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.store_path = Some(path);
+            for s in saved {
+                let state = if s.paused { JobState::Paused } else { JobState::Queued };
+                inner.add(s.job, state);
+            }
+        }
+        self.pump();
+        Ok(())
+    }
+
+    /// Adds a job and starts it straight away if a slot is free.
+    pub fn push(&self, job: DownloadJob) -> JobId {
+        let id = {
+            let mut inner = self.inner.lock().unwrap();
+            let id = inner.add(job, JobState::Queued);
+            inner.save();
+            id
+        };
+        self.pump();
+        id
+    }
+
+    /// Snapshot of all jobs in insertion order.
+    pub fn jobs(&self) -> Vec<JobEntry> {
+        self.inner.lock().unwrap().jobs.clone()
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    pub fn max_concurrency(&self) -> usize {
+        self.inner.lock().unwrap().max_concurrency
+    }
+
+    pub fn set_max_concurrency(&self, n: usize) {
+        self.inner.lock().unwrap().max_concurrency = n.max(1);
+        self.pump();
+    }
+
+    /// True when nothing is queued or running. Paused jobs don't count.
+    pub fn is_idle(&self) -> bool {
+        let inner = self.inner.lock().unwrap();
+        !inner
+            .jobs
+            .iter()
+            .any(|e| matches!(e.state, JobState::Queued | JobState::Running))
+    }
+
+    /// Drops done, failed and cancelled jobs from the list.
+    pub fn clear_finished(&self) {
+        self.inner.lock().unwrap().jobs.retain(|e| !e.state.is_finished());
+    }
+
+    /// Stops a queued, running or paused job. Finished jobs are left alone.
+    pub fn cancel(&self, id: JobId, request: CancelRequest) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.pausing.remove(&id);
+        if let Some(canceller) = inner.cancellers.get(&id) {
+            canceller.cancel(request);
+            return;
+        }
+
+        let Some(entry) = inner.jobs.iter_mut().find(|e| e.id == id) else {
+            return;
+        };
+        if matches!(entry.state, JobState::Queued | JobState::Paused) {
+            entry.state = JobState::Cancelled;
+            self.record(entry, "Cancelled", None);
+            inner.save();
+        }
+    }
+
+    /// Stops a job but keeps its partial file so `resume` can continue it.
+    pub fn pause(&self, id: JobId) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(canceller) = inner.cancellers.get(&id) {
+            canceller.cancel(CancelRequest { delete_partials: false });
+            inner.pausing.insert(id);
+            return;
+        }
+
+        if let Some(entry) = inner.jobs.iter_mut().find(|e| e.id == id)
+            && entry.state == JobState::Queued
+        {
+            entry.state = JobState::Paused;
+            inner.save();
+        }
+    }
+
+    pub fn resume(&self, id: JobId) {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            let Some(entry) = inner.jobs.iter_mut().find(|e| e.id == id) else {
+                return;
+            };
+            if entry.state != JobState::Paused {
+                return;
+            }
+            entry.state = JobState::Queued;
+            entry.log.push("Resumed".into());
+            inner.save();
+        }
+        self.pump();
+    }
+
+    fn pump(&self) {
+        // Jobs are started and their cancellers registered under the same lock
+        // that marks them running, so a pause or cancel can't slip in between.
+        let started: Vec<(JobId, DownloadHandle)> = {
+            let mut guard = self.inner.lock().unwrap();
+            let inner = &mut *guard;
+            let running = inner.jobs.iter().filter(|e| e.state == JobState::Running).count();
+            let free = inner.max_concurrency.saturating_sub(running);
+            let _rt = self.rt.enter();
+
+            inner
+                .jobs
+                .iter_mut()
+                .filter(|e| e.state == JobState::Queued)
+                .take(free)
+                .map(|e| {
+                    let backend = self.backends.pick(&e.job);
+                    e.state = JobState::Running;
+                    e.backend = Some(backend.name());
+                    let events = backend.start(e.job.clone());
+                    inner.cancellers.insert(e.id, events.canceller());
+                    (e.id, events)
+                })
+                .collect()
+        };
+
+        for (id, events) in started {
+            self.watch(id, events);
+        }
+    }
+
+    /// Applies a started job's events until its run ends, then fills the slot.
+    fn watch(&self, id: JobId, mut events: DownloadHandle) {
+        let queue = self.clone();
+        self.rt.spawn(async move {
+            while let Some(event) = events.next().await {
+                queue.apply(id, event);
+            }
+            queue.pump();
+        });
+    }
+
+    fn apply(&self, id: JobId, event: DownloadEvent) {
+        let mut guard = self.inner.lock().unwrap();
+        let inner = &mut *guard;
+        let Some(entry) = inner.jobs.iter_mut().find(|e| e.id == id) else {
+            return;
+        };
+
+        match event {
+            DownloadEvent::Started => {
+                entry.started_at.get_or_insert_with(Utc::now);
+                entry.log.push("Started".into());
+            }
+            DownloadEvent::Progress(p) => {
+                if let Some(fraction) = p.fraction() {
+                    entry.progress = fraction;
+                }
+                entry.transfer = Some(p);
+            }
+            DownloadEvent::Output(path) => entry.output = Some(path),
+            DownloadEvent::Log(line) => entry.log.push(line),
+            DownloadEvent::Stderr(line) => entry.stderr.push(line),
+            DownloadEvent::Finished(result) => {
+                // Dropped here rather than when the stream ends, so a job resumed
+                // right after this keeps the canceller of its new run.
+                inner.cancellers.remove(&id);
+                let pausing = inner.pausing.remove(&id);
+                let (state, status) = match &result {
+                    Ok(()) => (JobState::Done, "Completed".to_string()),
+                    Err(DownloadError::Cancelled) if pausing => (JobState::Paused, "Paused".to_string()),
+                    Err(DownloadError::Cancelled) => (JobState::Cancelled, "Cancelled".to_string()),
+                    Err(DownloadError::Failed { kind, .. }) if *kind != FailureKind::Unknown => {
+                        (JobState::Failed, format!("Failed: {}", kind.label()))
+                    }
+                    Err(_) => (JobState::Failed, "Failed".to_string()),
+                };
+                entry.state = state;
+                if state == JobState::Paused {
+                    entry.log.push("Paused".into());
+                } else {
+                    let exit_code = match &result {
+                        Ok(()) => Some(0),
+                        Err(e) => e.exit_code(),
+                    };
+                    if let Err(e) = result {
+                        entry.log.push(e.to_string());
+                        if let Some(hint) = e.hint() {
+                            entry.log.push(hint.into());
+                        }
+                        entry.error = Some(e);
+                    }
+                    self.record(entry, &status, exit_code);
+                }
+                inner.save();
+            }
+        }
+    }
+
+    fn record(&self, entry: &JobEntry, status: &str, exit_code: Option<i32>) {
+        let finished_at = Utc::now();
+        let bytes = entry.output.as_ref().and_then(|p| fs::metadata(p).ok()).map(|m| m.len());
+        let auth_failure = matches!(&entry.error, Some(DownloadError::Failed { kind, .. }) if kind.is_auth());
+
+        self.history.push(HistoryItem {
+            url: entry.job.url.clone(),
+            title: entry.job.title.clone(),
+            format: entry.job.format_label(),
+            audio: (entry.job.format == Format::AudioOnly).then_some(entry.job.audio),
+            status: status.into(),
+            started_at: entry.started_at.unwrap_or(finished_at),
+            finished_at,
+            output: entry.output.clone(),
+            bytes,
+            exit_code,
+            cookies: entry.job.cookies.as_ref().map(|c| c.name.clone()),
+            backend: entry.backend.map(String::from),
+            auth_failure,
+        });
+    }
+}
+
+impl Inner {
+    fn add(&mut self, job: DownloadJob, state: JobState) -> JobId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.jobs.push(JobEntry {
+            id,
+            job,
+            state,
+            progress: 0.0,
+            transfer: None,
+            log: Vec::new(),
+            stderr: Vec::new(),
+            error: None,
+            started_at: None,
+            output: None,
+            backend: None,
+        });
+        id
+    }
+
+    /// Writes every unfinished job to the queue file, if one is configured.
+    fn save(&self) {
+        let Some(path) = &self.store_path else {
+            return;
+        };
+        let saved: Vec<SavedJob> = self
+            .jobs
+            .iter()
+            .filter(|e| !e.state.is_finished())
+            .map(|e| SavedJob {
+                job: e.job.clone(),
+                paused: e.state == JobState::Paused,
+            })
+            .collect();
+
+        if let Err(e) = write_queue(path, &saved) {
+            eprintln!("Failed to save queue to {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn write_queue(path: &Path, saved: &[SavedJob]) -> io::Result<()> {
+    let bytes = serde_json::to_vec_pretty(saved).map_err(io::Error::other)?;
+    store::write_atomic(path, &bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{Backend, BoxFuture};
+    use crate::playlist::Media;
+    use std::thread;
+    use std::time::Duration;
+    use tokio::runtime::Runtime;
+
+    /// Finishes `…/done` jobs straight away; any other job runs until it is
+    /// cancelled or paused.
+    struct Stub;
+
+    impl Backend for Stub {
+        fn name(&self) -> &'static str {
+            "stub"
+        }
+
+        fn handles(&self, _job: &DownloadJob) -> bool {
+            true
+        }
+
+        fn inspect<'a>(&'a self, _url: &'a str) -> BoxFuture<'a, Result<Media, DownloadError>> {
+            Box::pin(async { Err(DownloadError::Cancelled) })
+        }
+
+        fn start(&self, job: DownloadJob) -> DownloadHandle {
+            let (handle, tx) = DownloadHandle::channel();
+            let canceller = handle.canceller();
+            tokio::spawn(async move {
+                let _ = tx.send(DownloadEvent::Started);
+                let result = if job.url.ends_with("/done") {
+                    Ok(())
+                } else {
+                    canceller.cancelled().await;
+                    Err(DownloadError::Cancelled)
+                };
+                let _ = tx.send(DownloadEvent::Finished(result));
+            });
+            handle
+        }
+    }
+
+    fn queue(rt: &Runtime, max_concurrency: usize) -> Queue {
+        let backends = Backends::new(vec![Arc::new(Stub)]);
+        Queue::new(backends, History::in_memory(), rt.handle().clone(), max_concurrency)
+    }
+
+    fn job(name: &str) -> DownloadJob {
+        DownloadJob::new(format!("https://example.com/{}", name), Format::BestVideo)
+    }
+
+    fn state(queue: &Queue, id: JobId) -> JobState {
+        queue.jobs().into_iter().find(|e| e.id == id).unwrap().state
+    }
+
+    fn wait_for(queue: &Queue, id: JobId, expected: JobState) {
+        for _ in 0..500 {
+            if state(queue, id) == expected {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("job {} is {:?}, not {:?}", id, state(queue, id), expected);
+    }
+
+    fn statuses(queue: &Queue) -> Vec<String> {
+        queue.history().items().into_iter().map(|i| i.status).collect()
+    }
+
+    #[test]
+    fn pause_and_resume_a_running_job() {
+        let rt = Runtime::new().unwrap();
+        let queue = queue(&rt, 1);
+        let a = queue.push(job("a"));
+        let b = queue.push(job("b"));
+        assert_eq!(state(&queue, a), JobState::Running);
+        assert_eq!(state(&queue, b), JobState::Queued);
+
+        queue.pause(a);
+        wait_for(&queue, a, JobState::Paused);
+        wait_for(&queue, b, JobState::Running);
+
+        queue.resume(a);
+        assert_eq!(state(&queue, a), JobState::Queued);
+        queue.cancel(b, CancelRequest { delete_partials: false });
+        wait_for(&queue, b, JobState::Cancelled);
+        wait_for(&queue, a, JobState::Running);
+
+        // Pausing isn't a result, so only the cancel is in the history.
+        assert_eq!(statuses(&queue), ["Cancelled"]);
+        assert!(!queue.is_idle());
+    }
+
+    #[test]
+    fn finished_jobs_free_their_slot() {
+        let rt = Runtime::new().unwrap();
+        let queue = queue(&rt, 1);
+        let a = queue.push(job("done"));
+        let b = queue.push(job("done"));
+        wait_for(&queue, a, JobState::Done);
+        wait_for(&queue, b, JobState::Done);
+        assert_eq!(statuses(&queue), ["Completed", "Completed"]);
+        assert!(queue.is_idle());
+    }
+
+    #[test]
+    fn pause_straight_after_start_is_not_lost() {
+        let rt = Runtime::new().unwrap();
+        let queue = queue(&rt, 20);
+        // Pauses each job the moment it shows up as running.
+        let pauser = queue.clone();
+        let paused = thread::spawn(move || {
+            for id in 1..=20 {
+                while pauser.jobs().iter().all(|e| e.id != id || e.state != JobState::Running) {
+                    thread::yield_now();
+                }
+                pauser.pause(id);
+            }
+        });
+        for i in 0..20 {
+            queue.push(job(&i.to_string()));
+        }
+        paused.join().unwrap();
+
+        for id in 1..=20 {
+            wait_for(&queue, id, JobState::Paused);
+        }
+        assert!(queue.is_idle());
+    }
+
+    #[test]
+    fn cancel_and_pause_jobs_that_are_not_running() {
+        let rt = Runtime::new().unwrap();
+        let queue = queue(&rt, 1);
+        let a = queue.push(job("a"));
+        let b = queue.push(job("b"));
+
+        queue.pause(b);
+        assert_eq!(state(&queue, b), JobState::Paused);
+        queue.pause(a);
+        wait_for(&queue, a, JobState::Paused);
+        // Paused jobs don't take a slot back by themselves.
+        assert_eq!(state(&queue, b), JobState::Paused);
+
+        queue.cancel(a, CancelRequest { delete_partials: false });
+        assert_eq!(state(&queue, a), JobState::Cancelled);
+        queue.resume(a);
+        assert_eq!(state(&queue, a), JobState::Cancelled);
+        assert_eq!(statuses(&queue), ["Cancelled"]);
+
+        queue.clear_finished();
+        assert_eq!(queue.jobs().len(), 1);
+    }
+
+    #[test]
+    fn restored_jobs_keep_their_state() {
+        let dir = std::env::temp_dir().join(format!("rstube-queue-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("queue.json");
+        let saved = [
+            SavedJob { job: job("paused"), paused: true },
+            SavedJob { job: job("queued"), paused: false },
+        ];
+        write_queue(&path, &saved).unwrap();
+
+        let rt = Runtime::new().unwrap();
+        let queue = queue(&rt, 1);
+        queue.persist_to(&path).unwrap();
+        let jobs = queue.jobs();
+        assert_eq!(jobs[0].state, JobState::Paused);
+        assert_eq!(jobs[1].state, JobState::Running);
+
+        queue.cancel(jobs[1].id, CancelRequest { delete_partials: false });
+        wait_for(&queue, jobs[1].id, JobState::Cancelled);
+        let left = read_saved(&path).unwrap();
+        assert_eq!(left.len(), 1);
+        assert!(left[0].paused);
+        assert_eq!(left[0].job.url, "https://example.com/paused");
+    }
+}
@@ -0,0 +1,205 @@
+// Downloading part of a video: time ranges and chapters passed to yt-dlp's
+// `--download-sections`, and start times taken from `?t=` links
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// One part of a video to fetch.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Section {
+    /// Seconds from the start; no end means to the end of the video.
+    Range { start: f64, end: Option<f64> },
+    /// A chapter, by its exact title.
+    Chapter(String),
+}
+
+impl Section {
+    /// Value for one `--download-sections` argument.
+    pub fn arg(&self) -> String {
+        match self {
+            Section::Range { start, end } => {
+                let end = end.map_or("inf".to_string(), |e| e.to_string());
+                format!("*{}-{}", start, end)
+            }
+            Section::Chapter(title) => format!("^{}$", regex_escape(title)),
+        }
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Section::Range { start, end: Some(end) } => write!(f, "{}–{}", clock(*start), clock(*end)),
+            Section::Range { start, end: None } => write!(f, "{}–end", clock(*start)),
+            Section::Chapter(title) => write!(f, "“{}”", title),
+        }
+    }
+}
+
+/// Parses a `start-end` range such as `1:00-2:30`; either side may be empty.
+pub fn parse_range(text: &str) -> Result<Section, String> {
+    let (start, end) = text
+        .split_once('-')
+        .ok_or_else(|| format!("'{}' is not a range like 1:00-2:30", text))?;
+    let start = match start.trim() {
+        "" => 0.0,
+        s => parse_time(s).ok_or_else(|| format!("'{}' is not a time", s))?,
+    };
+    let end = match end.trim() {
+        "" => None,
+        e => Some(parse_time(e).ok_or_else(|| format!("'{}' is not a time", e))?),
+    };
+    range(start, end)
+}
+
+/// A range, checked so the end comes after the start.
+pub fn range(start: f64, end: Option<f64>) -> Result<Section, String> {
+    if let Some(end) = end
+        && end <= start
+    {
+        return Err(format!("the end ({}) must come after the start ({})", clock(end), clock(start)));
+    }
+    Ok(Section::Range { start, end })
+}
+
+/// Reads `90`, `1:30`, `1:02:03` or `1h2m3s` as seconds.
+pub fn parse_time(text: &str) -> Option<f64> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    if text.contains(':') {
+        let mut secs = 0.0;
+        for (i, part) in text.split(':').enumerate() {
+            let value: f64 = part.trim().parse().ok()?;
+            // Only the leading part may run past 59, as in `90:00`.
+            if i > 2 || value < 0.0 || (i > 0 && value >= 60.0) {
+                return None;
+            }
+            secs = secs * 60.0 + value;
+        }
+        return Some(secs);
+    }
+    if let Ok(secs) = text.parse::<f64>() {
+        return (secs >= 0.0).then_some(secs);
+    }
+
+    // 1h2m3s, as YouTube's `t=` parameter allows.
+    let mut secs = 0.0;
+    let mut number = String::new();
+    for c in text.chars() {
+        match c {
+            '0'..='9' | '.' => number.push(c),
+            'h' | 'm' | 's' => {
+                let value: f64 = number.parse().ok()?;
+                number.clear();
+                secs += value
+                    * match c {
+                        'h' => 3600.0,
+                        'm' => 60.0,
+                        _ => 1.0,
+                    };
+            }
+            _ => return None,
+        }
+    }
+    number.is_empty().then_some(secs)
+}
+
+/// `3723.5` -> `"1:02:03.5"`; minutes and seconds only under an hour.
+pub fn clock(secs: f64) -> String {
+    let whole = secs.max(0.0) as u64;
+    let fraction = secs - whole as f64;
+    let (h, m, s) = (whole / 3600, whole / 60 % 60, whole % 60);
+    let mut out = if h > 0 { format!("{}:{:02}:{:02}", h, m, s) } else { format!("{}:{:02}", m, s) };
+    if fraction >= 0.05 {
+        out += &format!(".{}", (fraction * 10.0).round() as u64 % 10);
+    }
+    out
+}
+
+/// Start time from a link's `t=` or `start=` parameter, in the query or the fragment.
+pub fn start_time(link: &str) -> Option<f64> {
+    let url = url::Url::parse(link.trim()).ok()?;
+    let from_query = url
+        .query_pairs()
+        .find(|(k, _)| k == "t" || k == "start")
+        .and_then(|(_, v)| parse_time(&v));
+    from_query.or_else(|| {
+        url.fragment()?
+            .split('&')
+            .filter_map(|p| p.split_once('='))
+            .find(|(k, _)| *k == "t")
+            .and_then(|(_, v)| parse_time(v))
+    })
+}
+
+/// Adds the section's times to a yt-dlp output template, so a clip doesn't
+/// take the full video's file name. Templates not ending in `.%(ext)s` are
+/// left alone.
+pub(crate) fn tag_output(template: &str) -> String {
+    match template.strip_suffix(".%(ext)s") {
+        Some(stem) => format!("{} [%(section_start)s-%(section_end)s].%(ext)s", stem),
+        None => template.to_string(),
+    }
+}
+
+fn regex_escape(text: &str) -> String {
+    let mut out = String::new();
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$#&-~".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_times() {
+        assert_eq!(parse_time("90"), Some(90.0));
+        assert_eq!(parse_time("1:30"), Some(90.0));
+        assert_eq!(parse_time("1:02:03.5"), Some(3723.5));
+        assert_eq!(parse_time("90:00"), Some(5400.0));
+        assert_eq!(parse_time("1h2m3s"), Some(3723.0));
+        assert_eq!(parse_time("45s"), Some(45.0));
+    }
+
+    #[test]
+    fn rejects_bad_times() {
+        for text in ["", "abc", "-5", "1:60", "1:75:00", "1:00:60", "1:2:3:4", "1:-5", "5x", "1h2"] {
+            assert_eq!(parse_time(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(parse_range("1:00-2:30"), Ok(Section::Range { start: 60.0, end: Some(150.0) }));
+        assert_eq!(parse_range("-0:30"), Ok(Section::Range { start: 0.0, end: Some(30.0) }));
+        assert_eq!(parse_range("5:00-"), Ok(Section::Range { start: 300.0, end: None }));
+        assert!(parse_range("2:00-1:00").is_err());
+        assert!(parse_range("1:00").is_err());
+        assert!(parse_range("1:00-1:60").is_err());
+    }
+
+    #[test]
+    fn reads_start_times_from_links() {
+        assert_eq!(start_time("https://youtu.be/abc?t=90"), Some(90.0));
+        assert_eq!(start_time("https://www.youtube.com/watch?v=abc&t=1m30s"), Some(90.0));
+        assert_eq!(start_time("https://example.com/v?start=15"), Some(15.0));
+        assert_eq!(start_time("https://example.com/v#t=2:00"), Some(120.0));
+        assert_eq!(start_time("https://www.youtube.com/watch?v=abc"), None);
+        assert_eq!(start_time("not a link"), None);
+    }
+
+    #[test]
+    fn builds_download_sections_args() {
+        assert_eq!(Section::Range { start: 60.0, end: Some(90.5) }.arg(), "*60-90.5");
+        assert_eq!(Section::Range { start: 0.0, end: None }.arg(), "*0-inf");
+        assert_eq!(Section::Chapter("Intro (part 1)".into()).arg(), r"^Intro \(part 1\)$");
+    }
+}
@@ -0,0 +1,272 @@
+// User preferences, saved as TOML in the per-user config directory.
+// Every file carries a schema `version`; older files are migrated step by
+// step on load, so new settings can be added without breaking old configs.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use crate::audio::AudioOptions;
+use crate::cookies::CookieProfile;
+use crate::download::DownloadJob;
+use crate::embed::EmbedOptions;
+use crate::format::Format;
+use crate::network::NetworkOptions;
+use crate::sponsorblock::SponsorBlockOptions;
+use crate::store;
+use crate::subtitles::SubtitleOptions;
+use crate::template;
+use crate::tools::ToolPaths;
+use crate::video::VideoOptions;
+
+/// Schema version written by this build.
+pub const SETTINGS_VERSION: u32 = 2;
+
+/// yt-dlp's own default file name, in template syntax.
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "{title} [{id}].{ext}";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    pub output_dir: Option<PathBuf>,
+    pub format: Format,
+    /// Resolution, codec and container for video downloads.
+    pub video: VideoOptions,
+    /// How audio downloads are converted.
+    pub audio: AudioOptions,
+    /// Parallel downloads.
+    pub concurrency: usize,
+    /// File name, with placeholders from [`template`](crate::template).
+    pub filename_template: String,
+    /// Folder below `output_dir` for video downloads, e.g. `Videos/`.
+    pub video_folder: String,
+    /// Folder below `output_dir` for audio downloads, e.g. `Music/{uploader}/`.
+    pub audio_folder: String,
+    pub delete_partials: bool,
+    pub watch_clipboard: bool,
+    pub collect_mode: bool,
+    pub subtitles: SubtitleOptions,
+    pub sponsorblock: SponsorBlockOptions,
+    /// Proxy, bandwidth cap and retries for every yt-dlp run.
+    pub network: NetworkOptions,
+    /// yt-dlp and ffmpeg picked by hand instead of searched for.
+    pub tools: ToolPaths,
+    /// Imported cookies.txt files.
+    pub cookie_profiles: Vec<CookieProfile>,
+    /// Preferred profile where several cover the same site.
+    pub active_cookies: Option<String>,
+    /// What to embed into video downloads.
+    pub embed_video: EmbedOptions,
+    /// What to embed into audio downloads.
+    pub embed_audio: EmbedOptions,
+    /// Inner window size in points.
+    pub window_size: Option<[f32; 2]>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            output_dir: None,
+            format: Format::BestVideo,
+            video: VideoOptions::default(),
+            audio: AudioOptions::default(),
+            concurrency: 2,
+            filename_template: DEFAULT_FILENAME_TEMPLATE.into(),
+            video_folder: String::new(),
+            audio_folder: String::new(),
+            delete_partials: true,
+            watch_clipboard: false,
+            collect_mode: false,
+            subtitles: SubtitleOptions::default(),
+            sponsorblock: SponsorBlockOptions::default(),
+            network: NetworkOptions::default(),
+            tools: ToolPaths::default(),
+            cookie_profiles: Vec::new(),
+            active_cookies: None,
+            embed_video: EmbedOptions::default(),
+            embed_audio: EmbedOptions::default(),
+            window_size: None,
+        }
+    }
+}
+
+impl Settings {
+    /// `~/.config/rstube/config.toml` on Linux, and the equivalent elsewhere.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("rstube").join("config.toml"))
+    }
+
+    /// Reads settings from `path`; a missing file gives the defaults.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+        let table: toml::Table = toml::from_str(&text).map_err(io::Error::other)?;
+        migrate(table)?.try_into().map_err(io::Error::other)
+    }
+
+    /// Folder and file name template for a download in `format`, or `None`
+    /// for yt-dlp's default naming.
+    pub fn output_template(&self, format: &Format) -> Option<String> {
+        let folder = if format.is_audio_only() { &self.audio_folder } else { &self.video_folder };
+        let folder = folder.trim().trim_end_matches(['/', '\\']);
+        let name = self.filename_template.trim();
+
+        match (folder.is_empty(), name.is_empty()) {
+            (true, true) => None,
+            (true, false) => Some(name.to_string()),
+            (false, true) => Some(format!("{}/{}", folder, DEFAULT_FILENAME_TEMPLATE)),
+            (false, false) => Some(format!("{}/{}", folder, name)),
+        }
+    }
+
+    /// Embedding options for a download in `format`.
+    pub fn embed_for(&self, format: &Format) -> &EmbedOptions {
+        if format.is_audio_only() { &self.embed_audio } else { &self.embed_video }
+    }
+
+    pub fn embed_for_mut(&mut self, format: &Format) -> &mut EmbedOptions {
+        if format.is_audio_only() { &mut self.embed_audio } else { &mut self.embed_video }
+    }
+
+    /// Cookie profile for `url`: the active one if it covers the site,
+    /// otherwise the first that does. Sites no profile covers get none, so
+    /// cookies never go to a site they weren't exported for.
+    pub fn cookies_for(&self, url: &str) -> Option<&CookieProfile> {
+        let covering = || self.cookie_profiles.iter().filter(|p| p.covers(url));
+        covering()
+            .find(|p| Some(&p.name) == self.active_cookies.as_ref())
+            .or_else(|| covering().next())
+    }
+
+    /// A job for `url` in `format` with these settings' folder, templates,
+    /// cookies and video, audio, subtitle, SponsorBlock and embed options.
+    pub fn job(&self, url: impl Into<String>, format: Format) -> DownloadJob {
+        let url = url.into();
+        let template = self.output_template(&format);
+        let embed = *self.embed_for(&format);
+        let mut job = DownloadJob::new(url, format);
+        if let Some(profile) = self.cookies_for(&job.url) {
+            job = job.cookies(profile.clone());
+        }
+        if let Some(dir) = &self.output_dir {
+            job = job.output_dir(dir);
+        }
+        if let Some(template) = template {
+            job = job.output_template(template);
+        }
+        job.video(self.video)
+            .audio(self.audio)
+            .subtitles(self.subtitles.clone())
+            .sponsorblock(self.sponsorblock.clone())
+            .embed(embed)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let text = toml::to_string_pretty(self).map_err(io::Error::other)?;
+        store::write_atomic(path, text.as_bytes())
+    }
+}
+
+/// Brings a config written by any older version up to `SETTINGS_VERSION`.
+fn migrate(mut table: toml::Table) -> io::Result<toml::Table> {
+    let version = match table.get("version") {
+        None => 0,
+        Some(v) => v
+            .as_integer()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| io::Error::other("settings version is not a number"))?,
+    };
+    if version > SETTINGS_VERSION {
+        return Err(io::Error::other(format!(
+            "settings version {} is newer than this build supports ({})",
+            version, SETTINGS_VERSION
+        )));
+    }
+
+    for from in version..SETTINGS_VERSION {
+        match from {
+            // Unversioned files predate the schema and share v1's layout.
+            0 => {}
+            // v1 stored yt-dlp's raw output template.
+            1 => {
+                if table.get("filename_template").and_then(toml::Value::as_str) == Some(template::YTDLP_DEFAULT) {
+                    table.insert("filename_template".into(), DEFAULT_FILENAME_TEMPLATE.into());
+                }
+            }
+            _ => unreachable!("no migration from settings version {}", from),
+        }
+    }
+
+    table.insert("version".into(), toml::Value::Integer(SETTINGS_VERSION.into()));
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `text` to a config file of its own and loads it back.
+    fn load(name: &str, text: &str) -> (PathBuf, io::Result<Settings>) {
+        let dir = std::env::temp_dir().join(format!("rstube-settings-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        fs::write(&path, text).unwrap();
+        let settings = Settings::load(&path);
+        (path, settings)
+    }
+
+    #[test]
+    fn missing_file_gives_defaults() {
+        let path = std::env::temp_dir().join("rstube-settings-none").join("config.toml");
+        assert_eq!(Settings::load(&path).unwrap(), Settings::default());
+    }
+
+    #[test]
+    fn unversioned_file() {
+        let (_, settings) = load("v0", "concurrency = 4\nfilename_template = \"%(title)s [%(id)s].%(ext)s\"\n");
+        let settings = settings.unwrap();
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert_eq!(settings.concurrency, 4);
+        assert_eq!(settings.filename_template, DEFAULT_FILENAME_TEMPLATE);
+        assert_eq!(settings.network, NetworkOptions::default());
+    }
+
+    #[test]
+    fn v1_default_template_becomes_placeholders() {
+        let (_, settings) = load("v1-default", "version = 1\nfilename_template = \"%(title)s [%(id)s].%(ext)s\"\n");
+        assert_eq!(settings.unwrap().filename_template, "{title} [{id}].{ext}");
+    }
+
+    #[test]
+    fn v1_custom_template_is_kept() {
+        let (_, settings) = load("v1-custom", "version = 1\nfilename_template = \"%(uploader)s - %(title)s.%(ext)s\"\n");
+        assert_eq!(settings.unwrap().filename_template, "%(uploader)s - %(title)s.%(ext)s");
+    }
+
+    #[test]
+    fn newer_version_is_rejected_and_left_alone() {
+        let text = "version = 99\nconcurrency = 5\nsomething_new = true\n";
+        let (path, settings) = load("v99", text);
+        assert!(settings.unwrap_err().to_string().contains("newer than this build supports"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), text);
+    }
+
+    #[test]
+    fn round_trips() {
+        let settings = Settings {
+            concurrency: 3,
+            video_folder: "Videos".into(),
+            ..Settings::default()
+        };
+        let dir = std::env::temp_dir().join(format!("rstube-settings-save-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        settings.save(&path).unwrap();
+        assert_eq!(Settings::load(&path).unwrap(), settings);
+    }
+}
@@ -0,0 +1,35 @@
+// Recognising links to sites the app is meant for, e.g. in copied text
+
+/// Hosts, and their subdomains, whose links are offered for download.
+pub const SUPPORTED_HOSTS: &[&str] = &[
+    "youtube.com",
+    "youtu.be",
+    "youtube-nocookie.com",
+    "vimeo.com",
+    "dailymotion.com",
+    "twitch.tv",
+    "soundcloud.com",
+    "bandcamp.com",
+    "tiktok.com",
+    "x.com",
+    "twitter.com",
+    "instagram.com",
+];
+
+/// Returns the URL if `text` is a single http(s) link to a supported site.
+pub fn supported_url(text: &str) -> Option<String> {
+    let text = text.trim();
+    if text.contains(char::is_whitespace) {
+        return None;
+    }
+
+    let url = url::Url::parse(text).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let host = url.host_str()?.to_lowercase();
+    let supported = SUPPORTED_HOSTS
+        .iter()
+        .any(|h| host == *h || host.ends_with(&format!(".{}", h)));
+    supported.then(|| text.to_string())
+}
@@ -0,0 +1,166 @@
+// SponsorBlock: crowd-sourced sponsor, intro and self-promo segments that
+// yt-dlp can cut out or turn into chapters, plus a small client for the API
+// so the GUI can show what a video has before it is queued
+
+use serde::{Deserialize, Serialize};
+
+use crate::http;
+use crate::network::NetworkOptions;
+
+/// The public SponsorBlock server, also yt-dlp's default.
+pub const DEFAULT_API: &str = "https://sponsor.ajay.app";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Category {
+    Sponsor,
+    Intro,
+    Outro,
+    SelfPromo,
+    Interaction,
+    Preview,
+    Filler,
+    MusicOfftopic,
+}
+
+impl Category {
+    pub const ALL: [Category; 8] = [
+        Category::Sponsor,
+        Category::Intro,
+        Category::Outro,
+        Category::SelfPromo,
+        Category::Interaction,
+        Category::Preview,
+        Category::Filler,
+        Category::MusicOfftopic,
+    ];
+
+    /// Name used by the API and by yt-dlp.
+    pub fn api_name(&self) -> &'static str {
+        match self {
+            Category::Sponsor => "sponsor",
+            Category::Intro => "intro",
+            Category::Outro => "outro",
+            Category::SelfPromo => "selfpromo",
+            Category::Interaction => "interaction",
+            Category::Preview => "preview",
+            Category::Filler => "filler",
+            Category::MusicOfftopic => "music_offtopic",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Category::Sponsor => "Sponsor",
+            Category::Intro => "Intro",
+            Category::Outro => "Outro",
+            Category::SelfPromo => "Self-promo",
+            Category::Interaction => "Like/subscribe reminders",
+            Category::Preview => "Preview/recap",
+            Category::Filler => "Filler",
+            Category::MusicOfftopic => "Non-music in music videos",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SponsorBlockMode {
+    /// Cut the segments out of the file.
+    #[default]
+    Remove,
+    /// Keep them, but add a chapter for each.
+    Mark,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SponsorBlockOptions {
+    /// Nothing is fetched when empty.
+    pub categories: Vec<Category>,
+    pub mode: SponsorBlockMode,
+    /// SponsorBlock server, for mirrors or self-hosted instances.
+    pub api: String,
+}
+
+impl Default for SponsorBlockOptions {
+    fn default() -> Self {
+        Self {
+            categories: Vec::new(),
+            mode: SponsorBlockMode::Remove,
+            api: DEFAULT_API.into(),
+        }
+    }
+}
+
+impl SponsorBlockOptions {
+    pub fn is_enabled(&self) -> bool {
+        !self.categories.is_empty()
+    }
+
+    /// `sponsor,intro`, as the API and yt-dlp take them.
+    fn category_list(&self) -> String {
+        let names: Vec<&str> = self.categories.iter().map(Category::api_name).collect();
+        names.join(",")
+    }
+
+    /// yt-dlp arguments for these options.
+    pub fn args(&self) -> Vec<String> {
+        if !self.is_enabled() {
+            return Vec::new();
+        }
+        let flag = match self.mode {
+            SponsorBlockMode::Remove => "--sponsorblock-remove",
+            SponsorBlockMode::Mark => "--sponsorblock-mark",
+        };
+        let mut args = vec![flag.to_string(), self.category_list()];
+        let api = self.api.trim().trim_end_matches('/');
+        if !api.is_empty() && api != DEFAULT_API {
+            args.extend(["--sponsorblock-api".to_string(), api.to_string()]);
+        }
+        args
+    }
+}
+
+/// One segment as the API reports it.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Segment {
+    /// API name of the category, e.g. `sponsor`.
+    pub category: String,
+    /// Start and end in seconds.
+    pub segment: [f64; 2],
+}
+
+impl Segment {
+    pub fn length(&self) -> f64 {
+        (self.segment[1] - self.segment[0]).max(0.0)
+    }
+}
+
+/// Looks up the segments of `video_id` in the chosen categories, through the
+/// same proxy and local address as downloads. A video the server knows
+/// nothing about has no segments rather than an error.
+pub async fn segments(
+    options: &SponsorBlockOptions,
+    network: &NetworkOptions,
+    video_id: &str,
+) -> Result<Vec<Segment>, String> {
+    let api = match options.api.trim().trim_end_matches('/') {
+        "" => DEFAULT_API,
+        api => api,
+    };
+    let categories: Vec<&str> = options.categories.iter().map(Category::api_name).collect();
+    let categories = serde_json::to_string(&categories).map_err(|e| e.to_string())?;
+
+    let response = http::client(network)
+        .map_err(|e| e.to_string())?
+        .get(format!("{}/api/skipSegments", api))
+        .query(&[("videoID", video_id), ("categories", &categories)])
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(Vec::new());
+    }
+    let response = response.error_for_status().map_err(|e| e.to_string())?;
+    response.json().await.map_err(|e| e.to_string())
+}
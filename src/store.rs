@@ -0,0 +1,25 @@
+// On-disk locations and crash-safe file writes
+
+use std::{fs, io, io::Write, path::Path, path::PathBuf};
+
+/// Per-user data directory, e.g. `~/.local/share/rstube` on Linux.
+pub fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join("rstube"))
+}
+
+/// Writes `bytes` to a temporary file next to `path`, then renames it into
+/// place, so a crash never leaves a half-written file behind.
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
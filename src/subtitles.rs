@@ -0,0 +1,195 @@
+// Subtitle options: which languages to fetch, whether to keep them as sidecar
+// files or embed them, and plain-text transcripts made from the result
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SubtitleFormat {
+    #[default]
+    Srt,
+    Vtt,
+}
+
+impl SubtitleFormat {
+    pub fn ext(&self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "srt",
+            SubtitleFormat::Vtt => "vtt",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubtitleOptions {
+    /// Language codes, or yt-dlp patterns such as `en.*`. Empty means no subtitles.
+    pub languages: Vec<String>,
+    /// Fall back to YouTube's auto-generated captions.
+    pub auto_generated: bool,
+    pub format: SubtitleFormat,
+    /// Keep the subtitle files next to the video.
+    pub sidecar: bool,
+    /// Embed them into the MP4/MKV; audio downloads skip this.
+    pub embed: bool,
+    /// Also write the text without timings to a `.txt` file.
+    pub transcript: bool,
+}
+
+impl Default for SubtitleOptions {
+    fn default() -> Self {
+        Self {
+            languages: Vec::new(),
+            auto_generated: false,
+            format: SubtitleFormat::Srt,
+            sidecar: true,
+            embed: false,
+            transcript: false,
+        }
+    }
+}
+
+impl SubtitleOptions {
+    pub fn is_enabled(&self) -> bool {
+        !self.languages.is_empty()
+    }
+
+    /// yt-dlp arguments for these options.
+    pub fn args(&self) -> Vec<String> {
+        if !self.is_enabled() {
+            return Vec::new();
+        }
+        // Files are always written: the transcript is made from them, and
+        // they are removed afterwards if only embedding was asked for.
+        let mut args = vec!["--write-subs".to_string()];
+        if self.auto_generated {
+            args.push("--write-auto-subs".into());
+        }
+        args.extend(["--sub-langs".to_string(), self.languages.join(",")]);
+        args.extend(["--convert-subs".to_string(), self.format.ext().to_string()]);
+        if self.embed {
+            args.push("--embed-subs".into());
+        }
+        args
+    }
+}
+
+/// Path of a subtitle file yt-dlp announced in its output.
+pub(crate) fn parse_subtitle_file(line: &str) -> Option<PathBuf> {
+    line.strip_prefix("[info] Writing video subtitles to: ").map(PathBuf::from)
+}
+
+/// Where a subtitle file ended up after `--convert-subs`, if it still exists.
+pub(crate) fn converted(path: &Path, format: SubtitleFormat) -> Option<PathBuf> {
+    [path.with_extension(format.ext()), path.to_path_buf()]
+        .into_iter()
+        .find(|p| p.exists())
+}
+
+/// Writes the transcript of a subtitle file next to it and returns its path.
+pub(crate) fn write_transcript(subtitles: &Path) -> io::Result<PathBuf> {
+    let text = fs::read_to_string(subtitles)?;
+    let out = subtitles.with_extension("txt");
+    fs::write(&out, transcript(&text))?;
+    Ok(out)
+}
+
+/// Plain text of an SRT or WebVTT file, without cue numbers, timings or markup.
+pub fn transcript(subtitles: &str) -> String {
+    // Cue text is whatever follows a timing line up to the next empty line,
+    // so headers and NOTE, STYLE and REGION blocks are never read as text.
+    let mut cues: Vec<Vec<String>> = Vec::new();
+    let mut in_cue = false;
+    for raw in subtitles.lines() {
+        if raw.is_empty() {
+            in_cue = false;
+        } else if raw.contains("-->") {
+            cues.push(Vec::new());
+            in_cue = true;
+        } else if in_cue {
+            let text = strip_tags(raw);
+            let text = text.trim();
+            if !text.is_empty() {
+                cues.last_mut().unwrap().push(text.to_string());
+            }
+        }
+    }
+
+    // YouTube's rolling auto-captions carry each cue's last line over as the
+    // first line of the next one; only that copy is dropped, so dialogue that
+    // really repeats is kept.
+    let rolling = subtitles.trim_start_matches('\u{feff}').starts_with("WEBVTT");
+    let mut lines: Vec<&str> = Vec::new();
+    for (i, cue) in cues.iter().enumerate() {
+        let next = cues.get(i + 1).and_then(|c| c.first());
+        for (j, line) in cue.iter().enumerate() {
+            let carried = rolling && j + 1 == cue.len() && next == Some(line);
+            if !carried {
+                lines.push(line);
+            }
+        }
+    }
+
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
+
+/// Drops `<...>` markup such as `<i>` or VTT word timings, and decodes the
+/// few entities subtitles use.
+fn strip_tags(line: &str) -> String {
+    let mut out = String::new();
+    let mut in_tag = false;
+    for c in line.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srt_keeps_repeated_dialogue() {
+        let srt = "1\n00:00:01,000 --> 00:00:02,000\nYeah\n\n2\n00:00:02,000 --> 00:00:03,000\nYeah\n\n\
+                   3\n00:00:03,000 --> 00:00:04,000\n<i>ok</i>\n42\n";
+        assert_eq!(transcript(srt), "Yeah\nYeah\nok\n42\n");
+    }
+
+    #[test]
+    fn vtt_skips_header_note_and_style_blocks() {
+        let vtt = "WEBVTT\nKind: captions\n\nNOTE written by hand\nstill a note\n\nSTYLE\n::cue { color: red }\n\n\
+                   intro\n00:00.000 --> 00:01.000 align:start\nHello &amp; welcome\n\n\
+                   00:01.000 --> 00:02.000\n<v Bob>Hi</v>\nthere\n";
+        assert_eq!(transcript(vtt), "Hello & welcome\nHi\nthere\n");
+    }
+
+    #[test]
+    fn youtube_rolling_captions_are_not_doubled() {
+        let vtt = "WEBVTT\nKind: captions\nLanguage: en\n\n\
+                   00:00:00.560 --> 00:00:02.869 align:start position:0%\n \nhello<00:00:00.880><c> there</c>\n\n\
+                   00:00:02.869 --> 00:00:02.879 align:start position:0%\nhello there\n \n\n\
+                   00:00:02.879 --> 00:00:05.150 align:start position:0%\nhello there\nhow<00:00:03.199><c> are you</c>\n\n\
+                   00:00:05.150 --> 00:00:05.160 align:start position:0%\nhow are you\n \n\n\
+                   00:00:05.160 --> 00:00:07.000 align:start position:0%\nhow are you\nyeah yeah\n";
+        assert_eq!(transcript(vtt), "hello there\nhow are you\nyeah yeah\n");
+    }
+
+    #[test]
+    fn strips_markup_and_entities() {
+        assert_eq!(strip_tags("<i>a</i> <c.yellow>b</c>"), "a b");
+        assert_eq!(strip_tags("x<00:00:01.000><c> y</c>"), "x y");
+        assert_eq!(strip_tags("1 &lt; 2 &amp;&amp; 3&nbsp;&gt; 2"), "1 < 2 && 3 > 2");
+        assert_eq!(strip_tags("&amp;lt;"), "&lt;");
+    }
+}
@@ -0,0 +1,187 @@
+// Output templates: `{title}`-style placeholders that expand to yt-dlp's
+// `%(field)s` syntax, plus a local renderer for previews.
+//
+// Text outside braces is passed to yt-dlp untouched, so raw yt-dlp fields such
+// as `%(resolution)s` keep working. `{{` and `}}` write literal braces.
+
+use serde::{Deserialize, Serialize};
+
+use crate::info::VideoInfo;
+
+/// yt-dlp's output template when `-o` isn't given.
+pub const YTDLP_DEFAULT: &str = "%(title)s [%(id)s].%(ext)s";
+
+/// Placeholders a template may use, with a short description for the UI.
+pub const PLACEHOLDERS: &[(&str, &str)] = &[
+    ("title", "video title"),
+    ("id", "video id"),
+    ("uploader", "uploader name"),
+    ("channel", "channel name"),
+    ("upload_date", "upload date, YYYY-MM-DD"),
+    ("playlist", "playlist title"),
+    ("playlist_index", "position in the playlist"),
+    ("ext", "file extension"),
+];
+
+/// Where a job sits in the playlist it was picked from. yt-dlp only sees the
+/// single video URL, so these values are filled in before it runs.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistPosition {
+    pub title: Option<String>,
+    /// 1-based.
+    pub index: usize,
+}
+
+enum Piece<'a> {
+    Text(&'a str),
+    Field(&'a str),
+}
+
+/// Splits a template into literal text and placeholders.
+fn parse(template: &str) -> Result<Vec<Piece<'_>>, String> {
+    let mut pieces = Vec::new();
+    let mut rest = template;
+
+    while let Some(i) = rest.find(['{', '}']) {
+        if i > 0 {
+            pieces.push(Piece::Text(&rest[..i]));
+        }
+        rest = &rest[i..];
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            pieces.push(Piece::Text(&rest[..1]));
+            rest = &rest[2..];
+        } else if rest.starts_with('}') {
+            return Err("unmatched '}' (write '}}' for a literal brace)".into());
+        } else {
+            let end = rest.find('}').ok_or("unclosed '{'")?;
+            let name = rest[1..end].trim();
+            if !PLACEHOLDERS.iter().any(|(p, _)| *p == name) {
+                return Err(format!("unknown placeholder {{{}}}", name));
+            }
+            pieces.push(Piece::Field(name));
+            rest = &rest[end + 1..];
+        }
+    }
+    if !rest.is_empty() {
+        pieces.push(Piece::Text(rest));
+    }
+    Ok(pieces)
+}
+
+/// Checks a template for unknown placeholders and stray braces.
+pub fn validate(template: &str) -> Result<(), String> {
+    parse(template).map(|_| ())
+}
+
+/// Converts a template to yt-dlp's `-o` syntax. Playlist fields come from
+/// `playlist` when the job has one, otherwise yt-dlp fills them (or leaves
+/// them empty for a lone video). A template that doesn't parse is passed on
+/// as is.
+pub fn to_ytdlp(template: &str, playlist: Option<&PlaylistPosition>) -> String {
+    let Ok(pieces) = parse(template) else {
+        return template.to_string();
+    };
+
+    let mut out = String::new();
+    for piece in pieces {
+        match piece {
+            Piece::Text(text) => out.push_str(text),
+            Piece::Field(name) => match (name, playlist) {
+                ("playlist", Some(p)) => out.push_str(&escape(&sanitize(p.title.as_deref().unwrap_or("")))),
+                ("playlist_index", Some(p)) => out.push_str(&p.index.to_string()),
+                ("playlist" | "playlist_index", None) => {
+                    let field = if name == "playlist" { "playlist_title" } else { name };
+                    out.push_str(&format!("%({}|)s", field));
+                }
+                ("upload_date", _) => out.push_str("%(upload_date>%Y-%m-%d)s"),
+                _ => out.push_str(&format!("%({})s", name)),
+            },
+        }
+    }
+    out
+}
+
+/// Fills a template from already fetched metadata, the way yt-dlp would.
+/// Missing values show as `NA`, as they do in yt-dlp.
+pub fn render(
+    template: &str,
+    info: &VideoInfo,
+    playlist: Option<&PlaylistPosition>,
+    ext: &str,
+) -> Result<String, String> {
+    let mut out = String::new();
+    for piece in parse(template)? {
+        match piece {
+            Piece::Text(text) => out.push_str(text),
+            Piece::Field(name) => {
+                let value = match name {
+                    "title" => Some(info.title.clone()),
+                    "id" => Some(info.id.clone()),
+                    "uploader" | "channel" => info.uploader.clone(),
+                    "upload_date" => info.upload_date_pretty(),
+                    "playlist" => Some(playlist.and_then(|p| p.title.clone()).unwrap_or_default()),
+                    "playlist_index" => Some(playlist.map(|p| p.index.to_string()).unwrap_or_default()),
+                    "ext" => Some(ext.to_string()),
+                    _ => None,
+                };
+                out.push_str(&sanitize(value.as_deref().unwrap_or("NA")));
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Escapes `%` so yt-dlp reads a value literally.
+fn escape(value: &str) -> String {
+    value.replace('%', "%%")
+}
+
+/// Keeps a value from adding path components, as yt-dlp does.
+fn sanitize(value: &str) -> String {
+    value.replace(['/', '\\'], "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_placeholders() {
+        assert_eq!(to_ytdlp("{title} [{id}].{ext}", None), "%(title)s [%(id)s].%(ext)s");
+        assert_eq!(to_ytdlp("{upload_date} {uploader}", None), "%(upload_date>%Y-%m-%d)s %(uploader)s");
+        assert_eq!(to_ytdlp("{ title }.{ext}", None), "%(title)s.%(ext)s");
+    }
+
+    #[test]
+    fn keeps_literal_text() {
+        assert_eq!(to_ytdlp("%(resolution)s/{title}.{ext}", None), "%(resolution)s/%(title)s.%(ext)s");
+        assert_eq!(to_ytdlp("{{draft}} {title}", None), "{draft} %(title)s");
+    }
+
+    #[test]
+    fn playlist_fields_without_a_playlist() {
+        assert_eq!(
+            to_ytdlp("{playlist}/{playlist_index} - {title}", None),
+            "%(playlist_title|)s/%(playlist_index|)s - %(title)s"
+        );
+    }
+
+    #[test]
+    fn playlist_fields_from_the_job() {
+        let position = PlaylistPosition {
+            title: Some("Best of 100% / AC\\DC".into()),
+            index: 7,
+        };
+        assert_eq!(
+            to_ytdlp("{playlist}/{playlist_index} - {title}", Some(&position)),
+            "Best of 100%% _ AC_DC/7 - %(title)s"
+        );
+    }
+
+    #[test]
+    fn broken_templates_pass_through() {
+        assert_eq!(to_ytdlp("{nope}.{ext}", None), "{nope}.{ext}");
+        assert_eq!(to_ytdlp("{title", None), "{title");
+        assert!(validate("{title}}").is_err());
+    }
+}
@@ -0,0 +1,388 @@
+// Finding yt-dlp, ffmpeg and ffprobe: an explicit path from the settings, the
+// PATH, or a few common install locations, plus their versions and warnings
+// when they are too old for what rstube asks of them
+
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Paths picked by the user; `None` means search for the tool.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolPaths {
+    pub yt_dlp: Option<PathBuf>,
+    /// ffprobe is looked for next to this first.
+    pub ffmpeg: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tool {
+    YtDlp,
+    Ffmpeg,
+    Ffprobe,
+}
+
+impl Tool {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tool::YtDlp => "yt-dlp",
+            Tool::Ffmpeg => "ffmpeg",
+            Tool::Ffprobe => "ffprobe",
+        }
+    }
+
+    fn version_arg(&self) -> &'static str {
+        match self {
+            Tool::YtDlp => "--version",
+            Tool::Ffmpeg | Tool::Ffprobe => "-version",
+        }
+    }
+
+    fn file_name(&self) -> String {
+        if cfg!(windows) { format!("{}.exe", self.name()) } else { self.name().to_string() }
+    }
+}
+
+/// yt-dlp releases that added options rstube passes, oldest first.
+const YTDLP_FEATURES: &[((i32, u32, u32), &str)] = &[
+    ((2021, 10, 9), "progress reporting (--progress-template)"),
+    ((2022, 9, 1), "time ranges and chapters (--download-sections)"),
+];
+
+/// YouTube changes often enough that an older yt-dlp usually stops working.
+const YTDLP_STALE_DAYS: i64 = 180;
+
+/// Oldest ffmpeg yt-dlp's post-processors support.
+const FFMPEG_MINIMUM: (u32, u32) = (4, 0);
+
+/// How a tool was found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// Picked in the settings.
+    Chosen,
+    Path,
+    /// A common install folder that isn't on PATH.
+    Common,
+}
+
+impl Source {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Source::Chosen => "chosen",
+            Source::Path => "on PATH",
+            Source::Common => "found",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ToolStatus {
+    pub tool: Tool,
+    pub path: Option<PathBuf>,
+    pub source: Option<Source>,
+    /// First line of the version output, e.g. `2024.08.06` or `6.1.1-3ubuntu5`.
+    pub version: Option<String>,
+    /// Why the tool is missing or could not be run.
+    pub error: Option<String>,
+}
+
+impl ToolStatus {
+    /// Found and answered the version query.
+    pub fn is_ok(&self) -> bool {
+        self.path.is_some() && self.version.is_some()
+    }
+
+    /// Warnings about the version, if it is known and too old.
+    pub fn warnings(&self) -> Vec<String> {
+        let Some(version) = &self.version else {
+            return Vec::new();
+        };
+        match self.tool {
+            Tool::YtDlp => ytdlp_warnings(version, Utc::now().date_naive()),
+            Tool::Ffmpeg | Tool::Ffprobe => match ffmpeg_version(version) {
+                Some(v) if v < FFMPEG_MINIMUM => vec![format!(
+                    "{} {} is older than {}.{}, which yt-dlp needs for merging and conversion",
+                    self.tool.name(),
+                    version,
+                    FFMPEG_MINIMUM.0,
+                    FFMPEG_MINIMUM.1
+                )],
+                _ => Vec::new(),
+            },
+        }
+    }
+}
+
+/// yt-dlp, ffmpeg and ffprobe as found on this system.
+#[derive(Clone, Debug)]
+pub struct Toolchain {
+    pub yt_dlp: ToolStatus,
+    pub ffmpeg: ToolStatus,
+    pub ffprobe: ToolStatus,
+}
+
+impl Toolchain {
+    /// Locates each tool and asks it for its version.
+    pub async fn detect(paths: &ToolPaths) -> Self {
+        let ffmpeg = check(Tool::Ffmpeg, paths.ffmpeg.as_deref()).await;
+        // yt-dlp only takes one location for both, so prefer the ffprobe beside ffmpeg.
+        let beside = ffmpeg
+            .path
+            .as_ref()
+            .and_then(|p| p.parent())
+            .map(|d| d.join(Tool::Ffprobe.file_name()))
+            .filter(|p| p.is_file());
+        let ffprobe = match beside {
+            Some(p) => probe(Tool::Ffprobe, p, ffmpeg.source.unwrap_or(Source::Path)).await,
+            None => check(Tool::Ffprobe, None).await,
+        };
+        Self {
+            yt_dlp: check(Tool::YtDlp, paths.yt_dlp.as_deref()).await,
+            ffmpeg,
+            ffprobe,
+        }
+    }
+
+    pub fn tools(&self) -> [&ToolStatus; 3] {
+        [&self.yt_dlp, &self.ffmpeg, &self.ffprobe]
+    }
+
+    /// Merging, converting and embedding need both ffmpeg and ffprobe.
+    pub fn has_ffmpeg(&self) -> bool {
+        self.ffmpeg.is_ok() && self.ffprobe.is_ok()
+    }
+
+    /// Folder to pass as `--ffmpeg-location` when yt-dlp wouldn't find
+    /// ffmpeg on PATH by itself.
+    pub fn ffmpeg_location(&self) -> Option<PathBuf> {
+        match self.ffmpeg.source {
+            Some(Source::Chosen | Source::Common) => self.ffmpeg.path.as_ref()?.parent().map(Path::to_path_buf),
+            _ => None,
+        }
+    }
+
+    /// Everything worth telling the user: missing tools and old versions.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        for status in self.tools() {
+            match &status.error {
+                Some(e) => out.push(e.clone()),
+                None => out.extend(status.warnings()),
+            }
+        }
+        out
+    }
+}
+
+/// Where `tool` is: the explicit path if given, otherwise PATH, otherwise a
+/// common install folder.
+pub fn locate(tool: Tool, explicit: Option<&Path>) -> Option<(PathBuf, Source)> {
+    if let Some(path) = explicit {
+        return path.is_file().then(|| (path.to_path_buf(), Source::Chosen));
+    }
+    let name = tool.file_name();
+    if let Some(path) = std::env::var_os("PATH")
+        .into_iter()
+        .flat_map(|p| std::env::split_paths(&p).collect::<Vec<_>>())
+        .map(|dir| dir.join(&name))
+        .find(|p| p.is_file())
+    {
+        return Some((path, Source::Path));
+    }
+    common_dirs()
+        .into_iter()
+        .map(|dir| dir.join(&name))
+        .find(|p| p.is_file())
+        .map(|p| (p, Source::Common))
+}
+
+/// Install folders that are often missing from the PATH of a GUI app.
+fn common_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    // A copy shipped next to rstube itself.
+    if let Some(dir) = std::env::current_exe().ok().and_then(|p| p.parent().map(Path::to_path_buf)) {
+        dirs.push(dir);
+    }
+    if let Some(home) = dirs::home_dir() {
+        dirs.push(home.join(".local/bin"));
+        dirs.push(home.join("bin"));
+        dirs.push(home.join("scoop/shims"));
+    }
+    #[cfg(windows)]
+    if let Some(local) = dirs::data_local_dir() {
+        dirs.push(local.join("Microsoft/WinGet/Links"));
+    }
+    #[cfg(windows)]
+    dirs.push(PathBuf::from(r"C:\ffmpeg\bin"));
+    #[cfg(not(windows))]
+    dirs.extend(["/opt/homebrew/bin", "/usr/local/bin", "/usr/bin", "/snap/bin"].map(PathBuf::from));
+    dirs
+}
+
+/// Locates `tool` and runs it for its version.
+pub async fn check(tool: Tool, explicit: Option<&Path>) -> ToolStatus {
+    match locate(tool, explicit) {
+        Some((path, source)) => probe(tool, path, source).await,
+        None => ToolStatus {
+            tool,
+            path: None,
+            source: None,
+            version: None,
+            error: Some(match explicit {
+                Some(p) => format!("{} not found at {}", tool.name(), p.display()),
+                None => format!("{} not found on PATH or in the usual folders", tool.name()),
+            }),
+        },
+    }
+}
+
+async fn probe(tool: Tool, path: PathBuf, source: Source) -> ToolStatus {
+    let mut cmd = Command::new(&path);
+    cmd.arg(tool.version_arg())
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+
+    let (version, error) = match tokio::time::timeout(Duration::from_secs(10), cmd.output()).await {
+        Err(_) => (None, Some(format!("{} did not answer within 10 seconds", path.display()))),
+        Ok(Err(e)) => (None, Some(format!("Could not run {}: {}", path.display(), e))),
+        Ok(Ok(out)) => {
+            let text = String::from_utf8_lossy(&out.stdout);
+            match parse_version(tool, &text) {
+                Some(v) => (Some(v), None),
+                None => (None, Some(format!("{} gave no version; is it really {}?", path.display(), tool.name()))),
+            }
+        }
+    };
+    ToolStatus {
+        tool,
+        path: Some(path),
+        source: Some(source),
+        version,
+        error,
+    }
+}
+
+/// Version from `--version` / `-version` output.
+fn parse_version(tool: Tool, output: &str) -> Option<String> {
+    let line = output.lines().next()?.trim();
+    match tool {
+        Tool::YtDlp => (!line.is_empty()).then(|| line.to_string()),
+        // "ffmpeg version 6.1.1-3ubuntu5 Copyright (c) 2000-2023 ..."
+        Tool::Ffmpeg | Tool::Ffprobe => line
+            .strip_prefix(&format!("{} version ", tool.name()))?
+            .split_whitespace()
+            .next()
+            .map(str::to_string),
+    }
+}
+
+/// `2024.08.06` or a nightly's `2024.08.06.232516` as a date.
+fn ytdlp_date(version: &str) -> Option<NaiveDate> {
+    let mut parts = version.split('.').map(|p| p.parse::<u32>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+    NaiveDate::from_ymd_opt(year as i32, month, day)
+}
+
+fn ytdlp_warnings(version: &str, today: NaiveDate) -> Vec<String> {
+    let Some(date) = ytdlp_date(version) else {
+        return Vec::new();
+    };
+    let mut out: Vec<String> = YTDLP_FEATURES
+        .iter()
+        .filter(|((y, m, d), _)| NaiveDate::from_ymd_opt(*y, *m, *d).is_some_and(|since| date < since))
+        .map(|((y, m, d), feature)| {
+            format!("yt-dlp {} is too old for {}; it needs {}.{:02}.{:02} or newer", version, feature, y, m, d)
+        })
+        .collect();
+    let age = (today - date).num_days();
+    if age > YTDLP_STALE_DAYS {
+        out.push(format!(
+            "yt-dlp {} is {} days old and may no longer work with YouTube; run `yt-dlp -U` to update",
+            version, age
+        ));
+    }
+    out
+}
+
+/// Major and minor of an ffmpeg release; `None` for git builds such as
+/// `N-113000-g1234abcd`, whose age can't be told from the name.
+fn ffmpeg_version(version: &str) -> Option<(u32, u32)> {
+    let version = version.strip_prefix('n').unwrap_or(version);
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().and_then(|m| m.parse().ok()).unwrap_or(0);
+    Some((major, minor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn reads_versions() {
+        assert_eq!(parse_version(Tool::YtDlp, "2024.08.06\n").as_deref(), Some("2024.08.06"));
+        assert_eq!(parse_version(Tool::YtDlp, "\n"), None);
+        let ffmpeg = "ffmpeg version 6.1.1-3ubuntu5 Copyright (c) 2000-2023 the FFmpeg developers\nbuilt with gcc 13";
+        assert_eq!(parse_version(Tool::Ffmpeg, ffmpeg).as_deref(), Some("6.1.1-3ubuntu5"));
+        assert_eq!(parse_version(Tool::Ffprobe, "ffprobe version n6.1 Copyright").as_deref(), Some("n6.1"));
+        // ffprobe's output isn't taken for ffmpeg.
+        assert_eq!(parse_version(Tool::Ffmpeg, "ffprobe version 6.1"), None);
+        assert_eq!(parse_version(Tool::Ffmpeg, "usage: something else"), None);
+    }
+
+    #[test]
+    fn ytdlp_versions_are_dates() {
+        assert_eq!(ytdlp_date("2024.08.06"), Some(date(2024, 8, 6)));
+        assert_eq!(ytdlp_date("2024.08.06.232516"), Some(date(2024, 8, 6)));
+        assert_eq!(ytdlp_date("2024.13.01"), None);
+        assert_eq!(ytdlp_date("stable@2024"), None);
+    }
+
+    #[test]
+    fn warns_about_old_ytdlp() {
+        let today = date(2024, 9, 1);
+        assert!(ytdlp_warnings("2024.08.06", today).is_empty());
+        assert!(ytdlp_warnings("unknown", today).is_empty());
+
+        let stale = ytdlp_warnings("2023.12.30", today);
+        assert_eq!(stale.len(), 1);
+        assert!(stale[0].contains("246 days old"), "{}", stale[0]);
+
+        let ancient = ytdlp_warnings("2022.05.18", today);
+        assert_eq!(ancient.len(), 2, "{:?}", ancient);
+        assert!(ancient[0].contains("--download-sections"));
+
+        assert_eq!(ytdlp_warnings("2021.06.06", today).len(), 3);
+    }
+
+    #[test]
+    fn ffmpeg_release_numbers() {
+        assert_eq!(ffmpeg_version("6.1.1-3ubuntu5"), Some((6, 1)));
+        assert_eq!(ffmpeg_version("n6.1"), Some((6, 1)));
+        assert_eq!(ffmpeg_version("4.4.2-0ubuntu0.22.04.1"), Some((4, 4)));
+        assert_eq!(ffmpeg_version("7"), Some((7, 0)));
+        assert_eq!(ffmpeg_version("N-113000-g1234abcd"), None);
+    }
+
+    #[test]
+    fn warns_about_old_ffmpeg() {
+        let status = |version: &str| ToolStatus {
+            tool: Tool::Ffmpeg,
+            path: Some(PathBuf::from("/usr/bin/ffmpeg")),
+            source: Some(Source::Path),
+            version: Some(version.into()),
+            error: None,
+        };
+        assert_eq!(status("3.4.8").warnings().len(), 1);
+        assert!(status("4.0").warnings().is_empty());
+        assert!(status("N-113000-g1234abcd").warnings().is_empty());
+    }
+}
@@ -0,0 +1,189 @@
+// Video stream preferences, turned into a yt-dlp format sort (`-S`) so the
+// best stream is still picked, just within the limits the user set
+
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Which video codecs win when several are offered at the same resolution.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CodecOrder {
+    /// yt-dlp's own order.
+    #[default]
+    Any,
+    /// h264 > vp9 > av1: plays on older devices.
+    Compatible,
+    /// av1 > vp9 > h264: smaller files.
+    Efficient,
+}
+
+impl CodecOrder {
+    pub const ALL: [CodecOrder; 3] = [CodecOrder::Any, CodecOrder::Compatible, CodecOrder::Efficient];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CodecOrder::Any => "Any codec",
+            CodecOrder::Compatible => "H.264 > VP9 > AV1",
+            CodecOrder::Efficient => "AV1 > VP9 > H.264",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Container {
+    #[default]
+    Mp4,
+    Mkv,
+    Webm,
+}
+
+impl Container {
+    pub const ALL: [Container; 3] = [Container::Mp4, Container::Mkv, Container::Webm];
+
+    pub fn ext(&self) -> &'static str {
+        match self {
+            Container::Mp4 => "mp4",
+            Container::Mkv => "mkv",
+            Container::Webm => "webm",
+        }
+    }
+}
+
+impl FromStr for Container {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "mp4" => Ok(Container::Mp4),
+            "mkv" => Ok(Container::Mkv),
+            "webm" => Ok(Container::Webm),
+            _ => Err(format!("unknown container '{}'", s)),
+        }
+    }
+}
+
+/// How `Format::BestVideo` picks and merges streams.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VideoOptions {
+    /// Tallest resolution wanted, e.g. 1080; larger only if nothing smaller exists.
+    pub max_height: Option<u32>,
+    pub max_fps: Option<u32>,
+    pub codec_order: CodecOrder,
+    /// Allow HDR streams; off prefers SDR.
+    pub hdr: bool,
+    pub container: Container,
+}
+
+impl Default for VideoOptions {
+    fn default() -> Self {
+        Self {
+            max_height: None,
+            max_fps: None,
+            codec_order: CodecOrder::Any,
+            hdr: true,
+            container: Container::Mp4,
+        }
+    }
+}
+
+impl VideoOptions {
+    /// Heights offered in the UI.
+    pub const HEIGHTS: [u32; 7] = [2160, 1440, 1080, 720, 480, 360, 240];
+    /// Frame rates offered in the UI.
+    pub const FPS: [u32; 3] = [60, 30, 25];
+
+    /// Value for yt-dlp's `-S`, or `None` when yt-dlp's defaults already fit.
+    /// Fields go from most to least important: the resolution cap beats frame
+    /// rate, which beats HDR, codec and container.
+    pub fn sort_expression(&self) -> Option<String> {
+        let mut fields = Vec::new();
+        if let Some(h) = self.max_height {
+            fields.push(format!("res:{}", h));
+        }
+        if let Some(fps) = self.max_fps {
+            fields.push(format!("fps:{}", fps));
+        }
+        if !self.hdr {
+            fields.push("hdr:sdr".to_string());
+        }
+        // WebM can't hold H.264, so the compatible order stops at VP9 there.
+        let codec = match (self.codec_order, self.container) {
+            (CodecOrder::Any, _) => None,
+            (CodecOrder::Compatible, Container::Webm) => Some("vcodec:vp9"),
+            (CodecOrder::Compatible, _) => Some("vcodec:h264"),
+            (CodecOrder::Efficient, _) => Some("vcodec:av01"),
+        };
+        fields.extend(codec.map(String::from));
+        // Streams that already fit the container merge cleanly. MP4 only asks
+        // for it alongside other limits, so the defaults keep the best streams.
+        match self.container {
+            Container::Mp4 if !fields.is_empty() => fields.push("ext:mp4:m4a".into()),
+            Container::Webm => fields.push("ext:webm:webm".into()),
+            _ => {}
+        }
+
+        (!fields.is_empty()).then(|| fields.join(","))
+    }
+
+    /// yt-dlp arguments for a best-video download with these options.
+    pub fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(sort) = self.sort_expression() {
+            args.extend(["-S".to_string(), sort]);
+        }
+        args.extend(["--merge-output-format".to_string(), self.container.ext().to_string()]);
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_leave_sorting_to_yt_dlp() {
+        assert_eq!(VideoOptions::default().sort_expression(), None);
+        assert_eq!(VideoOptions::default().args(), ["--merge-output-format", "mp4"]);
+    }
+
+    #[test]
+    fn limits_come_first() {
+        let options = VideoOptions {
+            max_height: Some(1080),
+            max_fps: Some(30),
+            hdr: false,
+            codec_order: CodecOrder::Compatible,
+            ..VideoOptions::default()
+        };
+        assert_eq!(
+            options.sort_expression().as_deref(),
+            Some("res:1080,fps:30,hdr:sdr,vcodec:h264,ext:mp4:m4a")
+        );
+    }
+
+    #[test]
+    fn codec_order_follows_the_container() {
+        let webm = VideoOptions {
+            codec_order: CodecOrder::Compatible,
+            container: Container::Webm,
+            ..VideoOptions::default()
+        };
+        assert_eq!(webm.sort_expression().as_deref(), Some("vcodec:vp9,ext:webm:webm"));
+
+        let mkv = VideoOptions {
+            codec_order: CodecOrder::Efficient,
+            container: Container::Mkv,
+            ..VideoOptions::default()
+        };
+        assert_eq!(mkv.sort_expression().as_deref(), Some("vcodec:av01"));
+    }
+
+    #[test]
+    fn webm_alone_still_sorts() {
+        let webm = VideoOptions {
+            container: Container::Webm,
+            ..VideoOptions::default()
+        };
+        assert_eq!(webm.sort_expression().as_deref(), Some("ext:webm:webm"));
+    }
+}
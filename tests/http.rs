@@ -0,0 +1,187 @@
+// Direct downloads against a local mock server: whole files, resuming a
+// `.part` file, errors that aren't retried, SOCKS proxies and routing
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::{fs, thread};
+
+use rstube::{
+    Backend, Backends, DownloadEvent, DownloadJob, Downloader, EmbedOptions, Format, HttpBackend, NetworkOptions,
+};
+
+const BODY: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// Answers every request with `respond(request head)` and hands back the
+/// request heads it received.
+fn mock_server(respond: fn(&str) -> Vec<u8>) -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut reader = BufReader::new(stream.unwrap());
+            let mut head = String::new();
+            while reader.read_line(&mut head).unwrap() > 0 && !head.ends_with("\r\n\r\n") {}
+            reader.get_mut().write_all(&respond(&head)).unwrap();
+            let _ = tx.send(head);
+        }
+    });
+    (url, rx)
+}
+
+fn response(status: &str, headers: &str, body: &[u8]) -> Vec<u8> {
+    let mut out = format!(
+        "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n",
+        status,
+        body.len(),
+        headers
+    )
+    .into_bytes();
+    out.extend_from_slice(body);
+    out
+}
+
+/// Serves `BODY`, or the rest of it from where a `Range` header asks.
+fn ranged(head: &str) -> Vec<u8> {
+    let from = head
+        .lines()
+        .find_map(|l| l.to_lowercase().strip_prefix("range: bytes=")?.strip_suffix('-')?.parse::<usize>().ok());
+    match from {
+        Some(from) => {
+            let range = format!("Content-Range: bytes {}-{}/{}\r\n", from, BODY.len() - 1, BODY.len());
+            response("206 Partial Content", &range, &BODY[from..])
+        }
+        None => response("200 OK", "", BODY),
+    }
+}
+
+fn not_found(_: &str) -> Vec<u8> {
+    response("404 Not Found", "", b"gone")
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rstube-http-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+async fn run(backend: &HttpBackend, job: DownloadJob) -> Result<(), rstube::DownloadError> {
+    let mut events = backend.start(job);
+    while let Some(event) = events.next().await {
+        if let DownloadEvent::Finished(result) = event {
+            return result;
+        }
+    }
+    panic!("no Finished event");
+}
+
+#[tokio::test]
+async fn downloads_a_whole_file() {
+    let (url, requests) = mock_server(ranged);
+    let dir = temp_dir("whole");
+    let job = DownloadJob::new(format!("{}/clip.mp4", url), Format::BestVideo).output_dir(&dir);
+
+    run(&HttpBackend::default(), job).await.unwrap();
+
+    assert_eq!(fs::read(dir.join("clip.mp4")).unwrap(), BODY);
+    assert!(!dir.join("clip.mp4.part").exists());
+    assert!(!requests.recv().unwrap().to_lowercase().contains("range:"));
+}
+
+#[tokio::test]
+async fn resumes_a_part_file() {
+    let (url, requests) = mock_server(ranged);
+    let dir = temp_dir("resume");
+    fs::write(dir.join("clip.mp4.part"), &BODY[..10]).unwrap();
+    let job = DownloadJob::new(format!("{}/clip.mp4", url), Format::BestVideo).output_dir(&dir);
+
+    run(&HttpBackend::default(), job).await.unwrap();
+
+    assert_eq!(fs::read(dir.join("clip.mp4")).unwrap(), BODY);
+    assert!(requests.recv().unwrap().to_lowercase().contains("range: bytes=10-"));
+}
+
+#[tokio::test]
+async fn client_errors_are_not_retried() {
+    let (url, requests) = mock_server(not_found);
+    let dir = temp_dir("missing");
+    let job = DownloadJob::new(format!("{}/clip.mp4", url), Format::BestVideo).output_dir(&dir);
+
+    let error = run(&HttpBackend::default(), job).await.unwrap_err();
+
+    assert!(error.to_string().contains("404"), "{}", error);
+    requests.recv().unwrap();
+    assert!(requests.try_recv().is_err(), "a 404 was retried");
+    assert!(!dir.join("clip.mp4").exists());
+}
+
+/// A SOCKS5 proxy without authentication that serves one connection, and
+/// reports the port it was asked to connect to.
+fn socks5_proxy() -> (String, mpsc::Receiver<u16>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("socks5://{}", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let (mut client, _) = listener.accept().unwrap();
+        let mut greeting = [0u8; 2];
+        client.read_exact(&mut greeting).unwrap();
+        let mut methods = vec![0u8; greeting[1] as usize];
+        client.read_exact(&mut methods).unwrap();
+        client.write_all(&[5, 0]).unwrap();
+
+        // CONNECT to an IPv4 address; the mock server is on 127.0.0.1.
+        let mut request = [0u8; 10];
+        client.read_exact(&mut request).unwrap();
+        assert_eq!(&request[..4], &[5, 1, 0, 1]);
+        let port = u16::from_be_bytes([request[8], request[9]]);
+        let mut server = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        tx.send(port).unwrap();
+        client.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).unwrap();
+
+        let (mut from_client, mut to_server) = (client.try_clone().unwrap(), server.try_clone().unwrap());
+        thread::spawn(move || std::io::copy(&mut from_client, &mut to_server));
+        let _ = std::io::copy(&mut server, &mut client);
+    });
+    (url, rx)
+}
+
+#[tokio::test]
+async fn downloads_through_a_socks_proxy() {
+    let (url, requests) = mock_server(ranged);
+    let dir = temp_dir("socks");
+    let (proxy, connects) = socks5_proxy();
+    let backend = HttpBackend::default();
+    backend.set_network(&NetworkOptions {
+        proxy,
+        ..NetworkOptions::default()
+    });
+    let job = DownloadJob::new(format!("{}/clip.mp3", url), Format::BestVideo).output_dir(&dir);
+
+    run(&backend, job).await.unwrap();
+
+    assert_eq!(fs::read(dir.join("clip.mp3")).unwrap(), BODY);
+    requests.recv().unwrap();
+    let port: u16 = url.rsplit(':').next().unwrap().parse().unwrap();
+    assert_eq!(connects.try_recv(), Ok(port), "the proxy wasn't used");
+}
+
+#[test]
+fn only_plain_file_jobs_go_over_http() {
+    let backends = Backends::standard(Downloader::default());
+    let file = DownloadJob::new("https://example.com/media/clip.mp4", Format::BestVideo);
+
+    assert_eq!(backends.pick(&file).name(), "HTTP");
+    assert_eq!(backends.pick(&DownloadJob::new("https://youtu.be/abc", Format::BestVideo)).name(), "yt-dlp");
+    let tagged = file.clone().embed(EmbedOptions {
+        metadata: true,
+        ..EmbedOptions::default()
+    });
+    assert_eq!(backends.pick(&tagged).name(), "yt-dlp");
+    let audio = DownloadJob::new("https://example.com/media/clip.mp4", Format::AudioOnly);
+    assert_eq!(backends.pick(&audio).name(), "yt-dlp");
+}
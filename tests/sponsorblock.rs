@@ -0,0 +1,131 @@
+// SponsorBlock lookups and command lines, against a local mock of the API
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::thread;
+
+use rstube::sponsorblock::{self, Category, SponsorBlockMode, SponsorBlockOptions};
+use rstube::{DownloadJob, Downloader, Format, NetworkOptions};
+
+/// Serves one request with `status` and `body`, and hands back the request
+/// line it received.
+fn mock_api(status: &str, body: &str) -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        // Read the rest of the head so the client isn't cut off mid-request.
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 2 {
+            line.clear();
+        }
+        reader.get_mut().write_all(response.as_bytes()).unwrap();
+        tx.send(request_line.trim().to_string()).unwrap();
+    });
+    (url, rx)
+}
+
+fn options(api: &str) -> SponsorBlockOptions {
+    SponsorBlockOptions {
+        categories: vec![Category::Sponsor, Category::SelfPromo],
+        mode: SponsorBlockMode::Remove,
+        api: api.into(),
+    }
+}
+
+#[tokio::test]
+async fn segments_are_read_from_the_api() {
+    let body = r#"[
+        {"category": "sponsor", "segment": [10.0, 70.5], "UUID": "a", "actionType": "skip"},
+        {"category": "selfpromo", "segment": [300, 330], "UUID": "b", "actionType": "skip"}
+    ]"#;
+    let (api, requests) = mock_api("200 OK", body);
+
+    let segments = sponsorblock::segments(&options(&api), &NetworkOptions::default(), "dQw4w9WgXcQ").await.unwrap();
+
+    assert_eq!(segments.len(), 2);
+    assert_eq!(segments[0].category, "sponsor");
+    assert_eq!(segments[0].segment, [10.0, 70.5]);
+    assert_eq!(segments[1].length(), 30.0);
+
+    let request = requests.recv().unwrap();
+    assert!(request.starts_with("GET /api/skipSegments?"), "{}", request);
+    assert!(request.contains("videoID=dQw4w9WgXcQ"), "{}", request);
+    assert!(
+        request.contains("categories=%5B%22sponsor%22%2C%22selfpromo%22%5D"),
+        "{}",
+        request
+    );
+}
+
+#[tokio::test]
+async fn unknown_videos_have_no_segments() {
+    let (api, _requests) = mock_api("404 Not Found", "Not Found");
+    let segments = sponsorblock::segments(&options(&api), &NetworkOptions::default(), "nothing").await.unwrap();
+    assert!(segments.is_empty());
+}
+
+#[tokio::test]
+async fn lookups_go_through_the_proxy() {
+    let (proxy, requests) = mock_api("200 OK", "[]");
+    let network = NetworkOptions {
+        proxy,
+        ..NetworkOptions::default()
+    };
+
+    let segments = sponsorblock::segments(&options("http://sponsorblock.invalid"), &network, "abc").await.unwrap();
+
+    assert!(segments.is_empty());
+    let request = requests.recv().unwrap();
+    assert!(request.starts_with("GET http://sponsorblock.invalid/api/skipSegments?"), "{}", request);
+}
+
+#[tokio::test]
+async fn server_errors_are_reported() {
+    let (api, _requests) = mock_api("500 Internal Server Error", "oops");
+    assert!(sponsorblock::segments(&options(&api), &NetworkOptions::default(), "abc").await.is_err());
+}
+
+#[test]
+fn remove_mode_reaches_the_command_with_the_configured_api() {
+    let api = "http://127.0.0.1:8080";
+    let job = DownloadJob::new("https://youtu.be/abc", Format::BestVideo).sponsorblock(options(&format!("{}/", api)));
+
+    let args: Vec<String> = Downloader::default()
+        .command(&job)
+        .as_std()
+        .get_args()
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect();
+
+    let at = args.iter().position(|a| a == "--sponsorblock-remove").unwrap();
+    assert_eq!(args[at + 1], "sponsor,selfpromo");
+    let at = args.iter().position(|a| a == "--sponsorblock-api").unwrap();
+    assert_eq!(args[at + 1], api);
+}
+
+#[test]
+fn mark_mode_uses_chapters_and_the_default_api_is_implied() {
+    let mut options = options(sponsorblock::DEFAULT_API);
+    options.mode = SponsorBlockMode::Mark;
+    assert_eq!(options.args(), ["--sponsorblock-mark", "sponsor,selfpromo"]);
+}
+
+#[test]
+fn no_categories_means_no_sponsorblock() {
+    let mut options = options(sponsorblock::DEFAULT_API);
+    options.categories.clear();
+    assert!(options.args().is_empty());
+}